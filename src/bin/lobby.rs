@@ -0,0 +1,153 @@
+//! Minimal lobby for networked versus.
+//!
+//! Pairs clients in the order they connect, sends both the same seed and
+//! relays every line between them. Whenever one side reports `LOST`, the
+//! pair gets a fresh seed and starts over.
+//!
+//! ```sh
+//! cargo run --bin lobby -- 127.0.0.1:7878
+//! cargo run -- --connect 127.0.0.1:7878 # twice
+//! ```
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+};
+
+fn main() {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from("127.0.0.1:7878"));
+    let listener = TcpListener::bind(&addr).expect("failed to bind lobby address");
+    println!("lobby listening on {addr}");
+    serve(&listener);
+}
+
+/// Pairs up whoever connects to `listener`, for as long as it's open.
+fn serve(listener: &TcpListener) {
+    let mut waiting: Option<TcpStream> = None;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("failed to accept connection: {err}");
+                continue;
+            }
+        };
+        match waiting.take() {
+            None => {
+                println!("player waiting: {:?}", stream.peer_addr());
+                waiting = Some(stream);
+            }
+            Some(first) => {
+                println!("match found: {:?} vs {:?}", first.peer_addr(), stream.peer_addr());
+                std::thread::spawn(move || run_match([first, stream]));
+            }
+        }
+    }
+}
+
+fn run_match(players: [TcpStream; 2]) {
+    let Ok(writers) = players
+        .iter()
+        .map(TcpStream::try_clone)
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        eprintln!("failed to set up match");
+        return;
+    };
+    let writers = Arc::new(Mutex::new(writers));
+    start(&writers.lock().unwrap());
+
+    let handles = players.into_iter().enumerate().map(|(player, stream)| {
+        let writers = Arc::clone(&writers);
+        std::thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                let mut writers = writers.lock().unwrap();
+                let opponent = &mut writers[1 - player];
+                if writeln!(opponent, "{line}").is_err() {
+                    break;
+                }
+                if line.trim() == "LOST" {
+                    start(&writers);
+                }
+            }
+            println!("player {player} disconnected");
+        })
+    });
+    for handle in handles.collect::<Vec<_>>() {
+        let _ = handle.join();
+    }
+}
+
+fn start(writers: &[TcpStream]) {
+    let seed: u64 = rand::random();
+    println!("starting round with seed {seed}");
+    for mut writer in writers {
+        let _ = writeln!(writer, "START {seed}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{TcpListener, TcpStream},
+        time::Duration,
+    };
+
+    use super::serve;
+
+    struct Client {
+        stream: TcpStream,
+        lines: std::io::Lines<BufReader<TcpStream>>,
+    }
+
+    impl Client {
+        fn connect(addr: std::net::SocketAddr) -> Self {
+            let stream = TcpStream::connect(addr).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let lines = BufReader::new(stream.try_clone().unwrap()).lines();
+            Self { stream, lines }
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.stream, "{line}").unwrap();
+        }
+
+        fn receive(&mut self) -> String {
+            self.lines.next().unwrap().unwrap()
+        }
+    }
+
+    #[test]
+    fn pairs_clients_and_relays_lines_on_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || serve(&listener));
+
+        let mut first = Client::connect(addr);
+        let mut second = Client::connect(addr);
+        let start = first.receive();
+        assert!(start.starts_with("START "), "{start}");
+        assert_eq!(second.receive(), start);
+
+        first.send("INPUT 1 0 8 0");
+        assert_eq!(second.receive(), "INPUT 1 0 8 0");
+        second.send("GARBAGE 2");
+        assert_eq!(first.receive(), "GARBAGE 2");
+
+        // a loss is passed on, then both get the next round
+        second.send("LOST");
+        assert_eq!(first.receive(), "LOST");
+        let restart = first.receive();
+        assert!(restart.starts_with("START "), "{restart}");
+        assert_eq!(second.receive(), restart);
+    }
+}
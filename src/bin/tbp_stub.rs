@@ -0,0 +1,228 @@
+//! Tiny Tetris Bot Protocol bot, for trying out the `--tbp` frontend.
+//!
+//! Suggests every way to hard drop the current piece, lowest first, and
+//! tracks the board from `play`/`new_piece` like a real bot would.
+//!
+//! ```sh
+//! cargo build --bin tbp_stub
+//! cargo run -- --tbp target/debug/tbp_stub
+//! ```
+
+use std::io::{BufRead, Write};
+
+use tetris::tbp_messages::{
+    BotMessage, FrontendMessage, Move, Orientation, Piece, PieceLocation, Spin,
+};
+
+const COLS: i32 = 10;
+const ROWS: i32 = 40;
+/// Only suggest this many moves, the frontend uses the first it can do.
+const SUGGESTIONS: usize = 8;
+
+type Board = Vec<Vec<Option<char>>>;
+
+fn main() {
+    serve(std::io::stdin().lock(), std::io::stdout().lock());
+}
+
+/// Answers the frontend's messages on `input` until it quits.
+fn serve(input: impl BufRead, mut output: impl Write) {
+    send(
+        &mut output,
+        &BotMessage::Info {
+            name: String::from("tbp_stub"),
+            version: String::from(env!("CARGO_PKG_VERSION")),
+            author: String::from("tetris-with-bevy"),
+            features: Vec::new(),
+        },
+    );
+
+    let mut board: Board = Vec::new();
+    let mut queue = Vec::new();
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let message = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(err) => {
+                eprintln!("tbp_stub: ignoring {line}: {err}");
+                continue;
+            }
+        };
+        match message {
+            FrontendMessage::Rules {} => send(&mut output, &BotMessage::Ready),
+            FrontendMessage::Start {
+                queue: new_queue,
+                board: new_board,
+                ..
+            } => {
+                queue = new_queue;
+                board = new_board;
+            }
+            FrontendMessage::Stop => queue.clear(),
+            FrontendMessage::Suggest => send(
+                &mut output,
+                &BotMessage::Suggestion {
+                    moves: queue.first().map_or_else(Vec::new, |&x| suggest(&board, x)),
+                },
+            ),
+            FrontendMessage::Play { mv } => {
+                place(&mut board, &mv.location);
+                if !queue.is_empty() {
+                    queue.remove(0);
+                }
+            }
+            FrontendMessage::NewPiece { piece } => queue.push(piece),
+            FrontendMessage::Quit => break,
+        }
+    }
+}
+
+fn send(output: &mut impl Write, message: &BotMessage) {
+    let line = serde_json::to_string(message).expect("bot messages always encode");
+    // the frontend going away is the only way this fails, nothing to do then
+    let _ = writeln!(output, "{line}").and_then(|()| output.flush());
+}
+
+fn is_free(board: &Board, location: &PieceLocation) -> bool {
+    location.cells().iter().all(|&(x, y)| {
+        (0..COLS).contains(&x)
+            && (0..ROWS).contains(&y)
+            && board
+                .get(y as usize)
+                .and_then(|row| row.get(x as usize)?.as_ref())
+                .is_none()
+    })
+}
+
+/// Every hard drop from the top of the board, lowest landing first.
+fn suggest(board: &Board, piece: Piece) -> Vec<Move> {
+    let orientations = [
+        Orientation::North,
+        Orientation::East,
+        Orientation::South,
+        Orientation::West,
+    ];
+    let mut moves = orientations
+        .into_iter()
+        .flat_map(|orientation| (-1..=COLS).map(move |x| (orientation, x)))
+        .filter_map(|(orientation, x)| {
+            let mut location = PieceLocation {
+                piece,
+                orientation,
+                x,
+                y: 20,
+            };
+            if !is_free(board, &location) {
+                return None;
+            }
+            while location.y > 0
+                && is_free(
+                    board,
+                    &PieceLocation {
+                        y: location.y - 1,
+                        ..location
+                    },
+                )
+            {
+                location.y -= 1;
+            }
+            Some(location)
+        })
+        .collect::<Vec<_>>();
+    moves.sort_by_key(|x| x.cells().iter().map(|&(_, y)| y).max());
+    moves
+        .into_iter()
+        .take(SUGGESTIONS)
+        .map(|location| Move {
+            location,
+            spin: Spin::None,
+        })
+        .collect()
+}
+
+fn place(board: &mut Board, location: &PieceLocation) {
+    for (x, y) in location.cells() {
+        if let Some(cell) = board
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+        {
+            *cell = Some(location.piece.letter());
+        }
+    }
+    board.retain(|row| row.iter().any(Option::is_none));
+    board.resize(ROWS as usize, vec![None; COLS as usize]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What the stub answers to `messages`, followed by a `quit`.
+    fn answers(messages: &[FrontendMessage]) -> Vec<BotMessage> {
+        let input = messages
+            .iter()
+            .chain([&FrontendMessage::Quit])
+            .map(|x| serde_json::to_string(x).unwrap() + "\n")
+            .collect::<String>();
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output);
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|x| serde_json::from_str(x).unwrap())
+            .collect()
+    }
+
+    fn suggestion(answer: Option<&BotMessage>) -> Vec<Move> {
+        match answer {
+            Some(BotMessage::Suggestion { moves }) => moves.clone(),
+            other => panic!("expected a suggestion, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn plays_a_round() {
+        let start = FrontendMessage::Start {
+            hold: None,
+            queue: vec![Piece::T, Piece::O],
+            combo: 0,
+            back_to_back: false,
+            board: vec![vec![None; COLS as usize]; ROWS as usize],
+        };
+        let first = answers(&[
+            FrontendMessage::Rules {},
+            start.clone(),
+            FrontendMessage::Suggest,
+        ]);
+        assert!(matches!(first[0], BotMessage::Info { .. }));
+        assert_eq!(first[1], BotMessage::Ready);
+        let moves = suggestion(first.get(2));
+        assert!(!moves.is_empty() && moves.len() <= SUGGESTIONS);
+        assert!(moves.iter().all(|x| x.location.piece == Piece::T));
+        // lowest first, straight onto the floor
+        let played = moves[0];
+        assert_eq!(
+            played.location.cells().iter().map(|&(_, y)| y).min(),
+            Some(0)
+        );
+
+        let second = answers(&[
+            FrontendMessage::Rules {},
+            start,
+            FrontendMessage::Suggest,
+            FrontendMessage::Play { mv: played },
+            FrontendMessage::NewPiece { piece: Piece::I },
+            FrontendMessage::Suggest,
+        ]);
+        assert_eq!(suggestion(second.get(2)), moves);
+        let next = suggestion(second.get(3));
+        assert!(!next.is_empty());
+        for mv in next {
+            assert_eq!(mv.location.piece, Piece::O);
+            let cells = mv.location.cells();
+            assert!(played.location.cells().iter().all(|x| !cells.contains(x)));
+        }
+    }
+}
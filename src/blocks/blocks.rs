@@ -1,14 +1,23 @@
-use std::{convert::Infallible, time::Duration};
 
 use bevy::prelude::*;
-use rand_derive2::RandGen;
 
-use crate::{border::DrawBorderPlugin, schedule::InGameSet, GameState};
+use crate::{border::DrawBorderPlugin, schedule::InGameSet, settings::Settings, GameState};
 
 use super::{
     drawer::{DrawBlockEvent, DrawBoardPlugin},
+    finesse::FinessePlugin,
+    garbage::GarbagePlugin,
     gravity::GravityPlugin,
-    movement::MovementPlugin,
+    handling::Handling,
+    input::{
+        sample_input, step_touch, GamepadBindings, InputSource, KeyBindings, PlayerInput,
+        TickInput, TouchButtons,
+    },
+    mode::{format_time, GameClock, GameEnd, GameMode, ModePlugin},
+    movement::{block_movement_controls, MovementPlugin},
+    rng::GameRng,
+    skin::Mino,
+    stats::StatsPlugin,
 };
 
 pub const PREVIEW_COUNT: usize = 5;
@@ -17,34 +26,90 @@ pub const POINT_SIZE: f32 = 32.;
 pub struct TetrisBlockPlugin;
 
 impl Plugin for TetrisBlockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(TetrisSimulationPlugin)
+            .add_plugins(DrawBoardPlugin)
+            .add_plugins(DrawBorderPlugin)
+            .add_plugins(StatsPlugin)
+            .add_plugins(FinessePlugin)
+            .init_resource::<InputSource>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<GamepadBindings>()
+            .init_resource::<TouchButtons>()
+            .add_systems(
+                FixedUpdate,
+                (step_touch, sample_input)
+                    .chain()
+                    .run_if(resource_equals(InputSource::Keyboard))
+                    .in_set(InGameSet::UserInput),
+            )
+            .add_systems(
+                Update,
+                (
+                    info_gui,
+                    board_tui.run_if(|settings: Res<Settings>| settings.visuals.terminal_board),
+                )
+                    .in_set(InGameSet::BoardDrawer),
+            );
+    }
+}
+
+/// Just the game rules, driven by [`TickInput`] in `FixedUpdate`.
+///
+/// Doesn't touch the keyboard or any render resources, so it can also run
+/// headless in a separate `World`, e.g. to simulate a remote player.
+pub struct TetrisSimulationPlugin;
+
+impl Plugin for TetrisSimulationPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Board>()
+            .init_resource::<GameRng>()
             .init_resource::<CurrentBlockWithPreview<PREVIEW_COUNT>>()
             .init_resource::<Level>()
+            .init_resource::<StartLevel>()
             .init_resource::<Lines>()
             .init_resource::<Score>()
+            .init_resource::<TickInput>()
+            .init_resource::<Handling>()
+            .init_resource::<LineClear>()
+            .init_resource::<EntryDelay>()
             .add_event::<LinesIncrementEvent>()
+            .add_event::<PiecePlacedEvent>()
+            .add_event::<PieceDealtEvent>()
             .add_plugins(MovementPlugin)
             .add_plugins(GravityPlugin)
-            .add_plugins(DrawBoardPlugin)
-            .add_plugins(DrawBorderPlugin)
+            .add_plugins(GarbagePlugin)
+            .add_plugins(ModePlugin)
             .add_systems(
-                Update,
-                (block_spawner::<PREVIEW_COUNT>, clear_line).in_set(InGameSet::BoardInitUpdate),
+                FixedUpdate,
+                (clear_line, block_spawner::<PREVIEW_COUNT>)
+                    .chain()
+                    .in_set(InGameSet::BoardInitUpdate),
             )
-            .add_systems(Update, (level_up).in_set(InGameSet::InfoUpdate))
-            .add_systems(Update, (info_gui, board_tui).in_set(InGameSet::BoardDrawer))
-            .add_systems(OnEnter(GameState::GameOver), clear_board);
+            .add_systems(
+                FixedUpdate,
+                hold_block
+                    .in_set(InGameSet::EntityMovement)
+                    .before(block_movement_controls),
+            )
+            .add_systems(FixedUpdate, (level_up).in_set(InGameSet::InfoUpdate))
+            .add_systems(OnExit(GameState::GameOver), clear_board);
     }
 }
 
-fn clear_board(
+/// Resets everything for the next game when leaving the results.
+pub(crate) fn clear_board(
     mut board: ResMut<Board>,
+    mut line_clear: ResMut<LineClear>,
+    mut entry_delay: ResMut<EntryDelay>,
     mut level: ResMut<Level>,
+    start_level: Res<StartLevel>,
     mut lines: ResMut<Lines>,
     mut score: ResMut<Score>,
+    mut clock: ResMut<GameClock>,
+    mut end: ResMut<GameEnd>,
     mut preview: ResMut<CurrentBlockWithPreview<PREVIEW_COUNT>>,
-    mut next_state: ResMut<NextState<GameState>>,
+    mut rng: ResMut<GameRng>,
     mut entity: Query<Entity, With<Block>>,
     mut block_state: Query<&mut BlockState>,
     mut commands: Commands,
@@ -55,38 +120,123 @@ fn clear_board(
     for mut state in block_state.iter_mut() {
         *state = BlockState::Placed;
     }
-    *level = Level::default();
+    info!(
+        "game over with {} lines, seed {}",
+        lines.total_lines,
+        rng.seed()
+    );
+    *level = Level(start_level.0);
     *lines = Lines::default();
     *score = Score::default();
+    *clock = GameClock::default();
+    *end = GameEnd::default();
     *board = Board::default();
-    *preview = CurrentBlockWithPreview::default();
-    next_state.set(GameState::InGame);
+    *line_clear = LineClear::default();
+    *entry_delay = EntryDelay::default();
+    rng.next_game();
+    *preview = CurrentBlockWithPreview::new(&mut rng);
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub struct Level(pub u8);
 
 impl Default for Level {
+    fn default() -> Self {
+        Self(StartLevel::default().0)
+    }
+}
+
+/// [`Level`] every game starts at.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartLevel(pub u8);
+
+impl Default for StartLevel {
     fn default() -> Self {
         Self(9)
     }
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Clone)]
 pub struct Lines {
     total_lines: usize,
     current_level_lines: usize,
 }
 
+impl Lines {
+    pub const fn total(&self) -> usize {
+        self.total_lines
+    }
+}
+
 #[derive(Event, Default)]
-pub struct LinesIncrementEvent(usize);
+pub struct LinesIncrementEvent(pub usize);
+
+/// Full rows waiting out [`Handling::line_clear_delay`] before collapsing,
+/// nothing spawns meanwhile.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct LineClear {
+    pub rows: Vec<usize>,
+    /// Frames since they filled up.
+    pub frames: u32,
+}
+
+impl LineClear {
+    pub fn is_clearing(&self) -> bool {
+        !self.rows.is_empty()
+    }
+}
 
-fn level_up(
+/// Frames left before the next piece spawns once the line clears are done,
+/// see [`Handling::are`], and the buttons saved for it meanwhile.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct EntryDelay {
+    pub frames: u32,
+    /// Rotation and hold pressed while waiting, done as the piece spawns
+    /// with [`Handling::initial_actions`].
+    pub buffered: PlayerInput,
+}
+
+impl EntryDelay {
+    const INITIAL_ACTIONS: [PlayerInput; 4] = [
+        PlayerInput::ROTATE_CW,
+        PlayerInput::ROTATE_CCW,
+        PlayerInput::ROTATE_180,
+        PlayerInput::HOLD,
+    ];
+
+    /// Saves the rotation and hold pressed this tick, a rotation replacing
+    /// the one saved before.
+    fn buffer(&mut self, input: &TickInput) {
+        for button in Self::INITIAL_ACTIONS {
+            if !input.just_pressed(button) {
+                continue;
+            }
+            if button != PlayerInput::HOLD {
+                self.buffered.0 &= PlayerInput::HOLD.0;
+            }
+            self.buffered.0 |= button.0;
+        }
+    }
+}
+
+/// A piece locked and its line clears are done, sent before the next one
+/// spawns.
+#[derive(Event)]
+pub struct PiecePlacedEvent(pub Block);
+
+/// A new piece came out of the queue, to spawn or to take the place of the
+/// first one put on hold. Swapping with a held piece deals nothing.
+#[derive(Event)]
+pub struct PieceDealtEvent(pub Block);
+
+pub(super) fn level_up(
     mut lines_event: EventReader<LinesIncrementEvent>,
     mut lines: ResMut<Lines>,
     mut level: ResMut<Level>,
+    mut score: ResMut<Score>,
 ) {
     for i in lines_event.read().map(|x| x.0) {
+        score.0 += Score::for_lines(i) * (level.0 as usize + 1);
         if lines.current_level_lines < 10 {
             lines.current_level_lines += i;
         }
@@ -98,12 +248,31 @@ fn level_up(
     }
 }
 
-#[derive(Resource, Event, Default)]
+#[derive(Resource, Event, Default, Clone)]
 pub struct Score(usize);
 
-#[derive(Resource, Debug)]
+impl Score {
+    pub const fn value(&self) -> usize {
+        self.0
+    }
+
+    /// Points for clearing `lines` at once on level 0, as on the NES.
+    const fn for_lines(lines: usize) -> usize {
+        match lines {
+            0 => 0,
+            1 => 40,
+            2 => 100,
+            3 => 300,
+            _ => 1200,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
 pub struct Board {
     pub inner: Vec<Vec<BoardBlockState>>,
+    /// Pieces locked so far, the last one's cells are numbered this.
+    pub pieces: u32,
 }
 
 impl std::fmt::Display for Board {
@@ -116,8 +285,9 @@ impl std::fmt::Display for Board {
                     acc + &format!(
                         "[{}]",
                         match x {
-                            BoardBlockState::Placed { block_type }
+                            BoardBlockState::Placed { block_type, .. }
                             | BoardBlockState::Falling { block_type } => block_type.to_string(),
+                            BoardBlockState::Garbage => String::from("#"),
                             BoardBlockState::Empty => String::from(" "),
                         }
                     )
@@ -130,8 +300,16 @@ impl std::fmt::Display for Board {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BoardBlockState {
-    Placed { block_type: Block },
-    Falling { block_type: Block },
+    Placed {
+        block_type: Block,
+        /// Which piece the cell was, counting from 1 (see [`Board::pieces`]).
+        piece: u32,
+        links: Links,
+    },
+    Falling {
+        block_type: Block,
+    },
+    Garbage,
     Empty,
 }
 
@@ -142,7 +320,61 @@ impl BoardBlockState {
     }
     #[inline]
     pub const fn is_placed(self) -> bool {
-        matches!(self, Self::Placed { .. })
+        matches!(self, Self::Placed { .. } | Self::Garbage)
+    }
+    #[inline]
+    pub const fn piece(self) -> Option<u32> {
+        match self {
+            Self::Placed { piece, .. } => Some(piece),
+            _ => None,
+        }
+    }
+}
+
+/// Sides of a cell joined to another cell of the same piece. Placed cells
+/// keep theirs, so a piece split by a line clear stays apart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Links(pub u8);
+
+impl Links {
+    pub const UP: Self = Self(1 << 0);
+    pub const DOWN: Self = Self(1 << 1);
+    pub const LEFT: Self = Self(1 << 2);
+    pub const RIGHT: Self = Self(1 << 3);
+    pub const ALL: Self = Self(0b1111);
+
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Of the cell at `row` and `col`: what a placed cell kept, or for a
+    /// falling one the falling cells around it.
+    pub fn at(board: &[Vec<BoardBlockState>], row: usize, col: usize) -> Self {
+        match board[row][col] {
+            BoardBlockState::Placed { links, .. } => links,
+            BoardBlockState::Falling { .. } => {
+                let falling = |row: Option<usize>, col: Option<usize>| {
+                    row.zip(col)
+                        .and_then(|(row, col)| board.get(row)?.get(col))
+                        .is_some_and(|x| x.is_falling())
+                };
+                [
+                    (Self::UP, falling(row.checked_sub(1), Some(col))),
+                    (Self::DOWN, falling(Some(row + 1), Some(col))),
+                    (Self::LEFT, falling(Some(row), col.checked_sub(1))),
+                    (Self::RIGHT, falling(Some(row), Some(col + 1))),
+                ]
+                .into_iter()
+                .filter(|(_, linked)| *linked)
+                .fold(Self::default(), |acc, (side, _)| Self(acc.0 | side.0))
+            }
+            _ => Self::default(),
+        }
+    }
+
+    const fn without(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
     }
 }
 
@@ -154,6 +386,7 @@ impl Default for Board {
                     .map(|_| (0..10).map(|_| BoardBlockState::Empty).collect())
                     .collect()
             },
+            pieces: 0,
         }
     }
 }
@@ -171,8 +404,9 @@ pub enum BlockState {
 }
 
 impl Level {
-    pub const fn get_duraiton(&self) -> Duration {
-        let frames: u64 = match self.0 {
+    /// Frames it takes the piece to fall one row, as on the NES.
+    pub const fn gravity_frames(&self) -> u32 {
+        match self.0 {
             0 => 48,
             1 => 43,
             2 => 38,
@@ -188,8 +422,7 @@ impl Level {
             16..=18 => 3,
             19..=28 => 2,
             _ => 1,
-        };
-        Duration::from_millis(frames * 1000 / 60)
+        }
     }
 }
 
@@ -199,7 +432,7 @@ impl Default for BlockState {
     }
 }
 
-#[derive(Component, PartialEq, Eq, PartialOrd, Ord, Debug, RandGen, Clone, Copy)]
+#[derive(Component, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Hash)]
 pub enum Block {
     T,
     J,
@@ -210,30 +443,76 @@ pub enum Block {
     Z,
 }
 
+impl Block {
+    pub const ALL: [Self; 7] = [Self::T, Self::J, Self::L, Self::I, Self::O, Self::S, Self::Z];
+}
+
 #[derive(Resource, Clone)]
 pub struct CurrentBlockWithPreview<const T: usize> {
     current: Block,
     preview: [Block; T],
+    hold: Option<Block>,
+    /// Hold was already used on the current piece.
+    hold_used: bool,
 }
 
-impl<const T: usize> Default for CurrentBlockWithPreview<T> {
-    fn default() -> Self {
-        let preview = std::array::from_fn(|_| Block::generate_random());
+impl<const T: usize> FromWorld for CurrentBlockWithPreview<T> {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(&mut world.resource_mut::<GameRng>())
+    }
+}
+
+impl<const T: usize> CurrentBlockWithPreview<T> {
+    pub fn new(rng: &mut GameRng) -> Self {
+        let current = rng.next_block();
+        let preview = std::array::from_fn(|_| rng.next_block());
         Self {
-            current: Block::generate_random(),
+            current,
             preview,
+            hold: None,
+            hold_used: false,
         }
     }
-}
 
-impl<const T: usize> CurrentBlockWithPreview<T> {
-    fn get_and_generate_new_random(&mut self) -> Block {
+    pub const fn preview(&self) -> &[Block; T] {
+        &self.preview
+    }
+
+    pub const fn hold(&self) -> Option<Block> {
+        self.hold
+    }
+
+    /// The piece in play.
+    pub const fn current(&self) -> Block {
+        self.current
+    }
+
+    /// Hold wasn't used on the current piece yet.
+    pub const fn can_hold(&self) -> bool {
+        !self.hold_used
+    }
+
+    /// Puts the current piece on hold, once per piece. Returns what to
+    /// spawn instead: the previously held piece, or else the next one.
+    fn swap_hold(&mut self, rng: &mut GameRng) -> Option<Block> {
+        if self.hold_used {
+            return None;
+        }
+        let next = match self.hold.replace(self.current) {
+            Some(held) => held,
+            None => self.get_and_generate_new_random(rng),
+        };
+        self.current = next;
+        self.hold_used = true;
+        Some(next)
+    }
+
+    fn get_and_generate_new_random(&mut self, rng: &mut GameRng) -> Block {
         let original = self.preview[0];
         self.preview.rotate_left(1);
-        self.preview
-            .last_mut()
-            .map(|x| *x = Block::generate_random());
-        self.current = Block::generate_random();
+        self.preview.last_mut().map(|x| *x = rng.next_block());
+        self.current = original;
+        self.hold_used = false;
         original
     }
 }
@@ -256,18 +535,122 @@ impl std::fmt::Display for Block {
     }
 }
 
-fn block_spawner<const T: usize>(
+#[allow(clippy::too_many_arguments)]
+pub(super) fn block_spawner<const T: usize>(
     state: Query<&BlockState>,
     mut board: ResMut<Board>,
     mut commands: Commands,
     mut next_state: ResMut<NextState<GameState>>,
     mut current_block_with_preview: ResMut<CurrentBlockWithPreview<T>>,
+    mut rng: ResMut<GameRng>,
+    mut placed: EventWriter<PiecePlacedEvent>,
+    mut dealt: EventWriter<PieceDealtEvent>,
+    line_clear: Res<LineClear>,
+    mut entry_delay: ResMut<EntryDelay>,
+    input: Res<TickInput>,
+    handling: Res<Handling>,
+) {
+    if !state.iter().all(|&x| x == BlockState::Placed) {
+        return;
+    }
+    if line_clear.is_clearing() || entry_delay.frames > 0 {
+        if !line_clear.is_clearing() {
+            entry_delay.frames -= 1;
+        }
+        if handling.initial_actions {
+            entry_delay.buffer(&input);
+        }
+        return;
+    }
+    if !state.is_empty() {
+        placed.send(PiecePlacedEvent(current_block_with_preview.current()));
+    }
+    let block = current_block_with_preview.get_and_generate_new_random(&mut rng);
+    dealt.send(PieceDealtEvent(block));
+
+    if !board.spawn_block(block) {
+        next_state.set(GameState::GameOver);
+    }
+
+    /* Create the ground. */
+    commands.spawn(TetrisBlockBundle {
+        block,
+        state: BlockState::Falling,
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn hold_block(
+    input: Res<TickInput>,
+    mut board: ResMut<Board>,
+    mut pieces: Query<(&mut Block, &BlockState)>,
+    mut queue: ResMut<CurrentBlockWithPreview<PREVIEW_COUNT>>,
+    mut rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut dealt: EventWriter<PieceDealtEvent>,
+    entry_delay: Res<EntryDelay>,
+) {
+    if !input.just_pressed(PlayerInput::HOLD) && !entry_delay.buffered.contains(PlayerInput::HOLD) {
+        return;
+    }
+    let Some((mut block, _)) = pieces
+        .iter_mut()
+        .find(|(_, state)| **state == BlockState::Falling)
+    else {
+        return;
+    };
+    let from_queue = queue.hold().is_none();
+    let Some(next) = queue.swap_hold(&mut rng) else {
+        return;
+    };
+    if from_queue {
+        dealt.send(PieceDealtEvent(next));
+    }
+    board.clear_falling();
+    *block = next;
+    if !board.spawn_block(next) {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+pub(super) fn clear_line(
+    mut board: ResMut<Board>,
+    handling: Res<Handling>,
+    mut line_clear: ResMut<LineClear>,
+    mut lines: EventWriter<LinesIncrementEvent>,
 ) {
-    if state.iter().all(|&x| x == BlockState::Placed) {
-        let block = current_block_with_preview.get_and_generate_new_random();
+    if !line_clear.is_clearing() && handling.line_clear_delay > 0 {
+        let rows = board.full_rows();
+        if !rows.is_empty() {
+            *line_clear = LineClear { rows, frames: 0 };
+        }
+    }
+    if line_clear.is_clearing() {
+        line_clear.frames += 1;
+        if line_clear.frames <= handling.line_clear_delay {
+            return;
+        }
+        *line_clear = LineClear::default();
+    }
+    let move_down = board.clear_lines();
+    lines.send(LinesIncrementEvent(move_down));
+}
 
+impl Board {
+    /// Inserts `block` as the new falling piece at the top of the board.
+    /// Returns `false` if it overlapped something, i.e. the player topped out.
+    /// Takes the falling piece off the board.
+    pub fn clear_falling(&mut self) {
+        for cell in self.inner.iter_mut().flatten() {
+            if cell.is_falling() {
+                *cell = BoardBlockState::Empty;
+            }
+        }
+    }
+
+    pub fn spawn_block(&mut self, block: Block) -> bool {
         let array_to_insert = block.get_occupied();
-        let board_mid_point = board.inner.iter().map(Vec::len).max().unwrap() / 2;
+        let board_mid_point = self.inner.iter().map(Vec::len).max().unwrap() / 2;
         let offset = array_to_insert.iter().map(|x| x.len()).max().unwrap() / 2;
         let start_row = 0; // example starting row
         let start_col = board_mid_point - offset; // example starting column
@@ -275,58 +658,83 @@ fn block_spawner<const T: usize>(
         // Inserting the array into the vector
         for (i, row) in array_to_insert.iter().enumerate() {
             for (j, &elem) in row.iter().enumerate() {
-                if let Some(cell) = board
+                if let Some(cell) = self
                     .inner
                     .get_mut(start_row + i)
                     .and_then(|row| row.get_mut(start_col + j))
                 {
                     if cell != &BoardBlockState::Empty {
-                        next_state.set(GameState::GameOver);
-                        break;
+                        return false;
                     }
                     *cell = elem;
                 }
             }
         }
-
-        /* Create the ground. */
-        commands.spawn(TetrisBlockBundle {
-            block,
-            state: BlockState::Falling,
-        });
+        true
     }
-}
 
-fn clear_line(mut board: ResMut<Board>, mut lines: EventWriter<LinesIncrementEvent>) {
-    let board = &mut board.inner;
-    let p = board
-        .iter()
-        .enumerate()
-        .rev()
-        .filter(|(_, x)| x.iter().all(|x| x.is_placed()))
-        .map(|x| x.0)
-        .collect::<Vec<_>>();
+    /// Rows under the lowest square of `piece`, the whole board if it's
+    /// all cleared.
+    pub fn height_of(&self, piece: u32) -> usize {
+        let rows = self.inner.len();
+        self.inner
+            .iter()
+            .rposition(|row| row.iter().any(|x| x.piece() == Some(piece)))
+            .map_or(rows, |row| rows - 1 - row)
+    }
 
-    // clear the lines of impact
-    for i in p.iter() {
-        board[*i].iter_mut().for_each(|x| {
-            *x = BoardBlockState::Empty;
-        });
+    /// Rows filled with placed squares, bottom first.
+    pub fn full_rows(&self) -> Vec<usize> {
+        self.inner
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, x)| x.iter().all(|x| x.is_placed()))
+            .map(|x| x.0)
+            .collect()
     }
 
-    let move_down = p.iter().count();
-    lines.send(LinesIncrementEvent(move_down));
-    let starting = if move_down != 0 { p[0] } else { 0 };
+    /// Removes every full row, moving everything above down.
+    /// Returns the number of rows cleared.
+    pub fn clear_lines(&mut self) -> usize {
+        let p = self.full_rows();
+        let board = &mut self.inner;
+
+        // what's left of a piece cut by a clear doesn't join up again
+        for &i in &p {
+            for (row, side) in [(i.checked_sub(1), Links::DOWN), (Some(i + 1), Links::UP)] {
+                let Some(row) = row.and_then(|x| board.get_mut(x)) else {
+                    continue;
+                };
+                for cell in row {
+                    if let BoardBlockState::Placed { links, .. } = cell {
+                        *links = links.without(side);
+                    }
+                }
+            }
+        }
+
+        // clear the lines of impact
+        for i in p.iter() {
+            board[*i].iter_mut().for_each(|x| {
+                *x = BoardBlockState::Empty;
+            });
+        }
+
+        let move_down = p.iter().count();
+        let starting = if move_down != 0 { p[0] } else { 0 };
 
-    let cols = board[0].len();
+        let cols = board[0].len();
 
-    for _ in 0..move_down {
-        for row in (1..=starting).rev() {
-            for col in 0..cols {
-                let prev = board[row - 1][col];
-                board[row][col] = prev;
+        for _ in 0..move_down {
+            for row in (1..=starting).rev() {
+                for col in 0..cols {
+                    let prev = board[row - 1][col];
+                    board[row][col] = prev;
+                }
             }
         }
+        move_down
     }
 }
 
@@ -335,24 +743,40 @@ fn board_tui(
     lines: Res<Lines>,
     level: Res<Level>,
     preview: Res<CurrentBlockWithPreview<PREVIEW_COUNT>>,
+    rng: Res<GameRng>,
 ) {
     println!("{}", *board);
+    println!("seed: {}", rng.seed());
     println!("lines: {}", lines.total_lines);
     println!("level: {}", level.0);
     println!("next_piece: {}", preview.preview.first().unwrap());
 }
 
+/// The HUD text drawn by [`info_gui`], so it leaves other text alone.
+#[derive(Component)]
+struct InfoText;
+
+#[allow(clippy::too_many_arguments)]
 fn info_gui(
     lines: Res<Lines>,
     level: Res<Level>,
+    score: Res<Score>,
+    mode: Res<GameMode>,
+    clock: Res<GameClock>,
+    rng: Res<GameRng>,
     mut event: EventWriter<DrawBlockEvent>,
     preview: Res<CurrentBlockWithPreview<PREVIEW_COUNT>>,
-    mut query: Query<&mut Text>,
+    mut query: Query<&mut Text, With<InfoText>>,
     mut commands: Commands,
 ) {
     let value = format!(
-        "lines: {}\nlevel: {}\npreview: ",
-        lines.total_lines, level.0,
+        "{}\nseed: {}\nscore: {}\nlines: {}\nlevel: {}\ntime: {}\npreview: ",
+        mode.name(),
+        rng.seed(),
+        score.0,
+        lines.total_lines,
+        level.0,
+        format_time(clock.frames),
     );
     let transform = Transform::from_translation(Vec3::from_array([POINT_SIZE * 6., 0., 0.]));
     if query.is_empty() {
@@ -360,18 +784,21 @@ fn info_gui(
             font_size: POINT_SIZE,
             ..Default::default()
         };
-        commands.spawn(Text2dBundle {
-            text: Text {
-                sections: vec![TextSection {
-                    value,
-                    style: style,
-                }],
+        commands.spawn((
+            Text2dBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value,
+                        style: style,
+                    }],
+                    ..default()
+                },
+                text_anchor: bevy::sprite::Anchor::CenterLeft,
+                transform,
                 ..default()
             },
-            text_anchor: bevy::sprite::Anchor::CenterLeft,
-            transform,
-            ..default()
-        });
+            InfoText,
+        ));
     } else {
         for mut i in query.iter_mut() {
             for p in i.sections.iter_mut() {
@@ -386,9 +813,25 @@ fn info_gui(
             for col in 0..board[0].len() {
                 if board[row][col].is_falling() {
                     event.send(DrawBlockEvent {
-                        row: row + 3 + u,
-                        col: col + 16,
-                        block_type: *preview,
+                        row: (row + 3 + u) as isize,
+                        col: col as isize + 16,
+                        mino: Mino::Piece(*preview),
+                        links: Links::at(&board, row, col),
+                    });
+                }
+            }
+        }
+    }
+    if let Some(held) = preview.hold() {
+        let board = held.get_occupied();
+        for (row, cells) in board.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if cell.is_falling() {
+                    event.send(DrawBlockEvent {
+                        row: row as isize + 3,
+                        col: col as isize - 5,
+                        mino: Mino::Piece(held),
+                        links: Links::at(&board, row, col),
                     });
                 }
             }
@@ -2,6 +2,7 @@ use crate::blocks::blocks::Block;
 use bevy::prelude::*;
 
 use super::blocks::BoardBlockState;
+
 impl Block {
     pub const fn get_color(self) -> Color {
         match self {
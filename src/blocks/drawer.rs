@@ -1,86 +1,266 @@
 use bevy::{
     prelude::*,
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    utils::HashMap,
 };
+use serde::{Deserialize, Serialize};
 
-use crate::{border::Border, schedule::InGameSet};
+use crate::{schedule::InGameSet, settings::Settings};
 
-use super::blocks::{Block, Board, BoardBlockState, POINT_SIZE};
+use super::{
+    blocks::{Board, LineClear, Links, POINT_SIZE},
+    handling::Handling,
+    skin::{BlockAssets, Mino, SkinPlugin},
+};
 pub struct DrawBoardPlugin;
 
 impl Plugin for DrawBoardPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<DrawBlockEvent>()
+            .init_resource::<CellGrid>()
+            .add_plugins(SkinPlugin)
+            .add_systems(Update, draw_block.in_set(InGameSet::BoardDrawer))
+            // once everything in the set has said what to draw
             .add_systems(
                 Update,
-                (draw_single_block, draw_block).in_set(InGameSet::BoardDrawer),
-            )
-            .add_systems(Update, clear_blocks.after(InGameSet::BoardDrawer));
+                draw_single_block
+                    .after(InGameSet::BoardDrawer)
+                    .run_if(on_event::<DrawBlockEvent>()),
+            );
     }
 }
 
 #[derive(Event)]
 pub struct DrawBlockEvent {
-    pub row: usize,
-    pub col: usize,
-    pub block_type: Block,
+    pub row: isize,
+    pub col: isize,
+    pub mino: Mino,
+    /// Sides joined to the rest of the piece, see [`Links::at`].
+    pub links: Links,
 }
 
-fn clear_blocks(
-    mut commands: Commands,
-    block_mesh_handler: Query<Entity, (Without<Border>, With<Mesh2dHandle>)>,
-) {
-    for entity in &block_mesh_handler {
-        commands.entity(entity).despawn_recursive();
+/// How full rows go during [`Handling::line_clear_delay`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineClearStyle {
+    /// Squares vanish in pairs from the middle outwards, like on the NES.
+    #[default]
+    Wipe,
+    /// The rows blink.
+    Flash,
+    /// The rows are gone at once, what's above waits to fall.
+    Instant,
+}
+
+/// Frames the rows stay shown, then hidden, when flashing.
+const FLASH_FRAMES: u32 = 4;
+
+impl LineClearStyle {
+    /// Whether the square at `col` of a full row is hidden `frame` frames into
+    /// a clear taking `delay`.
+    const fn hides(self, col: usize, cols: usize, frame: u32, delay: u32) -> bool {
+        match self {
+            Self::Wipe => {
+                // pairs of squares counted from the middle, all gone by the end
+                let pair = (2 * col + 1).abs_diff(cols) / 2;
+                let pairs = cols.div_ceil(2);
+                pair as u32 * delay < frame * pairs as u32
+            }
+            Self::Flash => frame / FLASH_FRAMES % 2 == 1,
+            Self::Instant => true,
+        }
     }
 }
 
-fn draw_block(mut event: EventWriter<DrawBlockEvent>, board: Res<Board>) {
+fn draw_block(
+    mut event: EventWriter<DrawBlockEvent>,
+    board: Res<Board>,
+    line_clear: Res<LineClear>,
+    handling: Res<Handling>,
+    settings: Res<Settings>,
+) {
+    let style = settings.visuals.line_clear;
     for (u_row, row) in board.inner.iter().enumerate() {
+        let clearing = line_clear.rows.contains(&u_row);
         for (u_col, block) in row.iter().enumerate() {
-            match block {
-                BoardBlockState::Placed { block_type }
-                | BoardBlockState::Falling { block_type } => event.send(DrawBlockEvent {
-                    row: u_row,
-                    col: u_col,
-                    block_type: *block_type,
-                }),
-                BoardBlockState::Empty => {}
+            if clearing
+                && style.hides(
+                    u_col,
+                    row.len(),
+                    line_clear.frames,
+                    handling.line_clear_delay,
+                )
+            {
+                continue;
             }
+            if let Some(mino) = Mino::of(*block) {
+                event.send(DrawBlockEvent {
+                    row: u_row as isize,
+                    col: u_col as isize,
+                    mino,
+                    links: Links::at(&board.inner, u_row, u_col),
+                });
+            }
+        }
+    }
+}
+
+/// The cell entity drawn at each row and column, spawned the first time
+/// something is drawn there and kept from then on.
+#[derive(Resource, Default)]
+struct CellGrid(HashMap<(isize, isize), Entity>);
+
+/// A square of the board, preview, hold or opponent board, hidden while
+/// nothing is drawn there.
+#[derive(Component)]
+pub struct Cell {
+    look: Option<(Mino, Links)>,
+}
+
+impl Cell {
+    pub const fn new(mino: Mino, links: Links) -> Self {
+        Self {
+            look: Some((mino, links)),
+        }
+    }
+
+    pub const fn look(&self) -> Option<(Mino, Links)> {
+        self.look
+    }
+
+    pub const fn visibility(&self) -> Visibility {
+        if self.look.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
         }
     }
 }
 
+/// Updates only the cells that show something else than the last time, the
+/// events being everything that should be drawn now.
 fn draw_single_block(
     mut event: EventReader<DrawBlockEvent>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
+    mut grid: ResMut<CellGrid>,
+    mut cells: Query<(
+        &mut Cell,
+        &mut Visibility,
+        &mut Mesh2dHandle,
+        &mut Handle<ColorMaterial>,
+    )>,
+    assets: Res<BlockAssets>,
+    settings: Res<Settings>,
     mut commands: Commands,
 ) {
-    for DrawBlockEvent {
-        row,
-        col,
-        block_type,
-    } in event.read()
-    {
-        let block_mesh = meshes.add(Mesh::from(shape::Quad::default()));
-        let color = block_type.get_color();
-        let material = materials.add(ColorMaterial::from(color));
+    let mut drawn = event
+        .read()
+        .map(|x| {
+            // unlinked sides are only drawn apart for connected pieces
+            let links = if settings.visuals.connected {
+                x.links
+            } else {
+                Links::ALL
+            };
+            ((x.row, x.col), (x.mino, links))
+        })
+        .collect::<HashMap<_, _>>();
+    for (position, &entity) in &grid.0 {
+        let look = drawn.remove(position);
+        let Ok((mut cell, mut visibility, mut mesh, mut material)) = cells.get_mut(entity) else {
+            continue;
+        };
+        if cell.look == look {
+            continue;
+        }
+        if let Some((mino, links)) = look {
+            (*mesh, *material) = assets.look(mino, links);
+        }
+        cell.look = look;
+        *visibility = cell.visibility();
+    }
+
+    for ((row, col), (mino, links)) in drawn {
+        let (mesh, material) = assets.look(mino, links);
         let transform = Transform::default()
             .with_scale(Vec3::from_array([POINT_SIZE, POINT_SIZE, POINT_SIZE]))
             .with_translation(Vec3::from_array([
-                POINT_SIZE * *col as f32 - POINT_SIZE * 4.,
-                -POINT_SIZE * *row as f32 + POINT_SIZE * 10.,
+                POINT_SIZE * col as f32 - POINT_SIZE * 4.,
+                -POINT_SIZE * row as f32 + POINT_SIZE * 10.,
                 0.,
             ]));
 
         let mesh_bundle = MaterialMesh2dBundle {
-            mesh: block_mesh.into(),
+            mesh,
             transform,
             material,
             ..default()
         };
 
-        commands.spawn(mesh_bundle);
+        let entity = commands.spawn((mesh_bundle, Cell::new(mino, links))).id();
+        grid.0.insert((row, col), entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::blocks::{Block, BoardBlockState};
+
+    const BLOCKS: [Block; 7] = [
+        Block::T,
+        Block::J,
+        Block::L,
+        Block::I,
+        Block::O,
+        Block::S,
+        Block::Z,
+    ];
+
+    #[test]
+    fn redrawing_never_adds_assets() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .init_resource::<Settings>()
+            .init_resource::<Board>()
+            .init_resource::<LineClear>()
+            .insert_resource(Handling {
+                line_clear_delay: 20,
+                ..default()
+            })
+            .add_plugins(DrawBoardPlugin);
+        app.update();
+        let meshes = app.world.resource::<Assets<Mesh>>().len();
+        let materials = app.world.resource::<Assets<ColorMaterial>>().len();
+        assert!(meshes > 0 && materials > 0);
+
+        for frame in 0..300_usize {
+            let mut board = app.world.resource_mut::<Board>();
+            if frame % 60 == 59 {
+                *board = Board::default();
+            } else {
+                let (row, col) = (frame * 7 % 20, frame * 3 % 10);
+                board.inner[row][col] = match frame % 9 {
+                    0 => BoardBlockState::Garbage,
+                    1 => BoardBlockState::Empty,
+                    x => BoardBlockState::Falling {
+                        block_type: BLOCKS[x % BLOCKS.len()],
+                    },
+                };
+            }
+            // a row being wiped now and then
+            let mut line_clear = app.world.resource_mut::<LineClear>();
+            line_clear.rows = if frame % 40 < 20 { vec![19] } else { vec![] };
+            line_clear.frames = (frame % 20) as u32;
+            app.update();
+        }
+
+        assert!(!app.world.resource::<CellGrid>().0.is_empty());
+        assert_eq!(app.world.resource::<Assets<Mesh>>().len(), meshes);
+        assert_eq!(
+            app.world.resource::<Assets<ColorMaterial>>().len(),
+            materials
+        );
     }
 }
@@ -0,0 +1,445 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::{schedule::InGameSet, settings::Settings, GameState};
+
+use super::{
+    blocks::{hold_block, Block, BlockState, Board, BoardBlockState, EntryDelay, POINT_SIZE},
+    gravity::hard_drop,
+    input::{InputSource, PlayerInput, TickInput},
+    mode::GameMode,
+    movement::{block_movement_controls, move_left, move_right, rotate_block, SpeedTimer},
+    stats::Stats,
+};
+
+/// Longest sequence searched for, every placement on an empty board takes
+/// fewer presses.
+const MAX_PRESSES: usize = 6;
+
+/// One button press in a placement, held or not. Hard drops aren't counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Press {
+    Left,
+    Right,
+    /// Left held until the piece stops.
+    DasLeft,
+    DasRight,
+    Cw,
+    Ccw,
+    Flip,
+}
+
+impl Press {
+    const ALL: [Self; 7] = [
+        Self::DasLeft,
+        Self::DasRight,
+        Self::Left,
+        Self::Right,
+        Self::Cw,
+        Self::Ccw,
+        Self::Flip,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Left => "L",
+            Self::Right => "R",
+            Self::DasLeft => "DAS L",
+            Self::DasRight => "DAS R",
+            Self::Cw => "CW",
+            Self::Ccw => "CCW",
+            Self::Flip => "180",
+        }
+    }
+
+    /// What `self` does to the falling `block`, the same way the game moves it.
+    fn apply(self, board: &mut Vec<Vec<BoardBlockState>>, block: Block) {
+        let cols = board[0].len();
+        match self {
+            Self::Left => move_left(board, block),
+            Self::Right => move_right(board, block),
+            Self::DasLeft => (0..cols).for_each(|_| move_left(board, block)),
+            Self::DasRight => (0..cols).for_each(|_| move_right(board, block)),
+            _ if block == Block::O => {}
+            Self::Cw => rotate_block(board, &block, true),
+            Self::Ccw => rotate_block(board, &block, false),
+            Self::Flip => {
+                rotate_block(board, &block, true);
+                rotate_block(board, &block, true);
+            }
+        }
+    }
+}
+
+fn falling_cells(board: &[Vec<BoardBlockState>]) -> Vec<(usize, usize)> {
+    cells(board, |x| x.is_falling())
+}
+
+fn cells(
+    board: &[Vec<BoardBlockState>],
+    filter: impl Fn(BoardBlockState) -> bool,
+) -> Vec<(usize, usize)> {
+    board
+        .iter()
+        .enumerate()
+        .flat_map(|(row, cells)| {
+            cells
+                .iter()
+                .enumerate()
+                .filter(|(_, &cell)| filter(cell))
+                .map(move |(col, _)| (row, col))
+        })
+        .collect()
+}
+
+/// Cells `block` locks into when hard dropped from where it is on `board`.
+fn landing(board: &[Vec<BoardBlockState>], block: Block) -> Vec<(usize, usize)> {
+    let mut dropped = board.to_vec();
+    // numbered apart from every piece already placed
+    hard_drop(&mut dropped, block, 0);
+    cells(&dropped, |x| x.piece() == Some(0))
+}
+
+/// Fewest presses that hard drop the falling `block` on `board` into
+/// `target`, searched breadth first over the moves and rotations the game
+/// itself makes. `None` if it takes a soft drop (tucks and spins) or more
+/// than [`MAX_PRESSES`].
+pub fn optimal_presses(
+    board: &[Vec<BoardBlockState>],
+    block: Block,
+    target: &[(usize, usize)],
+) -> Option<Vec<Press>> {
+    let mut seen = HashSet::from([falling_cells(board)]);
+    let mut queue = VecDeque::from([(board.to_vec(), Vec::new())]);
+    while let Some((board, presses)) = queue.pop_front() {
+        if landing(&board, block) == target {
+            return Some(presses);
+        }
+        if presses.len() == MAX_PRESSES {
+            continue;
+        }
+        for press in Press::ALL {
+            let mut next = board.clone();
+            press.apply(&mut next, block);
+            if seen.insert(falling_cells(&next)) {
+                let mut presses = presses.clone();
+                presses.push(press);
+                queue.push_back((next, presses));
+            }
+        }
+    }
+    None
+}
+
+/// How the last placement went.
+#[derive(Debug, Clone)]
+pub struct Verdict {
+    pub block: Block,
+    pub presses: u32,
+    pub optimal: Vec<Press>,
+}
+
+impl Verdict {
+    pub fn is_fault(&self) -> bool {
+        self.presses as usize > self.optimal.len()
+    }
+}
+
+/// Follows the piece in play to tell whether it was placed with as few
+/// presses as possible.
+#[derive(Resource, Default)]
+pub struct Finesse {
+    /// The board as the piece in play spawned (or came out of hold).
+    spawned: Option<Vec<Vec<BoardBlockState>>>,
+    /// Moves and rotations pressed on the piece in play.
+    presses: u32,
+    pub last: Option<Verdict>,
+}
+
+/// Flags finesse faults in the [`Stats`], and with `training.finesse_trainer`
+/// shows the optimal presses for each piece. `training.finesse_retry` puts a
+/// faulted piece back at the top to try again.
+pub struct FinessePlugin;
+
+impl Plugin for FinessePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Finesse>()
+            .add_systems(
+                FixedUpdate,
+                count_presses
+                    .after(hold_block)
+                    .before(block_movement_controls)
+                    .in_set(InGameSet::EntityMovement),
+            )
+            .add_systems(FixedUpdate, judge_placement.in_set(InGameSet::InfoUpdate))
+            .add_systems(
+                Update,
+                finesse_trainer
+                    .run_if(|settings: Res<Settings>| settings.training.finesse_trainer)
+                    .in_set(InGameSet::BoardDrawer),
+            )
+            .add_systems(OnExit(GameState::GameOver), reset_finesse);
+    }
+}
+
+fn count_presses(
+    new_piece: Query<(), Changed<Block>>,
+    board: Res<Board>,
+    input: Res<TickInput>,
+    entry_delay: Res<EntryDelay>,
+    mut finesse: ResMut<Finesse>,
+) {
+    if !new_piece.is_empty() {
+        finesse.spawned = Some(board.inner.clone());
+        finesse.presses = 0;
+    }
+    // rotations pressed before the piece spawned are done on it now
+    finesse.presses += [
+        PlayerInput::LEFT,
+        PlayerInput::RIGHT,
+        PlayerInput::ROTATE_CW,
+        PlayerInput::ROTATE_CCW,
+        PlayerInput::ROTATE_180,
+    ]
+    .into_iter()
+    .filter(|&button| input.just_pressed(button) || entry_delay.buffered.contains(button))
+    .count() as u32;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn judge_placement(
+    mut locked: Query<(&Block, &mut BlockState), Changed<BlockState>>,
+    mut board: ResMut<Board>,
+    mut finesse: ResMut<Finesse>,
+    mut stats: ResMut<Stats>,
+    mut speed: ResMut<SpeedTimer>,
+    settings: Res<Settings>,
+    source: Res<InputSource>,
+    mode: Res<GameMode>,
+) {
+    let Some((&block, mut state)) = locked.iter_mut().find(|(_, x)| **x == BlockState::Placed)
+    else {
+        return;
+    };
+    let Some(spawned) = &finesse.spawned else {
+        return;
+    };
+    let piece = board.pieces;
+    let target = cells(&board.inner, |x| x.piece() == Some(piece));
+    // tucks, spins and slides have no finesse to speak of
+    let Some(optimal) = optimal_presses(spawned, block, &target) else {
+        return;
+    };
+    let verdict = Verdict {
+        block,
+        presses: finesse.presses,
+        optimal,
+    };
+    if verdict.is_fault() {
+        stats.finesse_faults += 1;
+        // a retry isn't part of the game rules, so it can't be replayed or
+        // simulated for an opponent
+        let retry = settings.training.finesse_retry
+            && *source == InputSource::Keyboard
+            && *mode != GameMode::Versus;
+        if retry {
+            board.inner = spawned.clone();
+            board.pieces -= 1;
+            *state = BlockState::Falling;
+            speed.frames = 0;
+            finesse.presses = 0;
+        }
+    }
+    finesse.last = Some(verdict);
+}
+
+fn reset_finesse(mut finesse: ResMut<Finesse>) {
+    *finesse = Finesse::default();
+}
+
+#[derive(Component)]
+struct TrainerText;
+
+fn finesse_trainer(
+    finesse: Res<Finesse>,
+    mut text: Query<&mut Text, With<TrainerText>>,
+    mut commands: Commands,
+) {
+    let (value, color) = match &finesse.last {
+        Some(verdict) => {
+            let optimal = verdict
+                .optimal
+                .iter()
+                .map(|x| x.name())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let value = format!(
+                "{}: {} presses, best {} ({})",
+                verdict.block,
+                verdict.presses,
+                verdict.optimal.len(),
+                if optimal.is_empty() { "none" } else { &optimal },
+            );
+            let color = if verdict.is_fault() {
+                Color::RED
+            } else {
+                Color::GREEN
+            };
+            (value, color)
+        }
+        None => (String::new(), Color::WHITE),
+    };
+    let Ok(mut text) = text.get_single_mut() else {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    value,
+                    TextStyle {
+                        font_size: POINT_SIZE * 0.75,
+                        color,
+                        ..default()
+                    },
+                ),
+                transform: Transform::from_translation(Vec3::new(
+                    POINT_SIZE * 0.5,
+                    -POINT_SIZE * 10.5,
+                    0.,
+                )),
+                ..default()
+            },
+            TrainerText,
+        ));
+        return;
+    };
+    text.sections[0].value = value;
+    text.sections[0].style.color = color;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffered_rotations_count_as_presses() {
+        let mut app = App::new();
+        app.init_resource::<Board>()
+            .init_resource::<TickInput>()
+            .init_resource::<Finesse>()
+            .insert_resource(EntryDelay {
+                frames: 0,
+                buffered: PlayerInput::ROTATE_CW,
+            })
+            .add_systems(Update, count_presses);
+        app.world.spawn((Block::T, BlockState::Falling));
+        app.world
+            .resource_mut::<TickInput>()
+            .advance(PlayerInput::LEFT);
+        app.update();
+        assert_eq!(app.world.resource::<Finesse>().presses, 2);
+    }
+
+    /// `block` just spawned on an empty board `cols` wide.
+    fn spawned(block: Block, cols: usize) -> Vec<Vec<BoardBlockState>> {
+        let mut board = Board {
+            inner: vec![vec![BoardBlockState::Empty; cols]; 20],
+            pieces: 0,
+        };
+        assert!(board.spawn_block(block));
+        board.inner
+    }
+
+    /// Where the falling `block` lands after `presses`.
+    fn after(
+        board: &[Vec<BoardBlockState>],
+        block: Block,
+        presses: &[Press],
+    ) -> Vec<(usize, usize)> {
+        let mut board = board.to_vec();
+        for press in presses {
+            press.apply(&mut board, block);
+        }
+        landing(&board, block)
+    }
+
+    fn left_edge(cells: &[(usize, usize)]) -> usize {
+        cells.iter().map(|&(_, col)| col).min().unwrap()
+    }
+
+    #[test]
+    fn fewest_presses_for_each_piece_and_column() {
+        // by column from the left wall, as the pieces spawn
+        let three_wide = [1, 2, 2, 1, 0, 1, 2, 1];
+        let counts: [(Block, &[usize]); 7] = [
+            (Block::T, &three_wide),
+            (Block::J, &three_wide),
+            (Block::L, &three_wide),
+            (Block::S, &three_wide),
+            (Block::Z, &three_wide),
+            (Block::O, &[1, 2, 2, 1, 0, 1, 2, 2, 1]),
+            // rotating by transposition, a 180 shifts a flat I a column right
+            (Block::I, &[1, 2, 1, 0, 1, 1, 1]),
+        ];
+        for (block, counts) in counts {
+            let board = spawned(block, 10);
+            for (col, &count) in counts.iter().enumerate() {
+                let target = after(
+                    &board,
+                    block,
+                    &[&[Press::DasLeft], &vec![Press::Right; col][..]].concat(),
+                );
+                assert_eq!(left_edge(&target), col);
+                let optimal = optimal_presses(&board, block, &target).unwrap();
+                assert_eq!(optimal.len(), count, "{block} at column {col}: {optimal:?}");
+                assert_eq!(after(&board, block, &optimal), target);
+            }
+            let last = after(&board, block, &[Press::DasRight]);
+            assert_eq!(left_edge(&last), counts.len() - 1);
+
+            // turned, it's never more than a rotation on top
+            for rotation in [Press::Cw, Press::Ccw, Press::Flip] {
+                for col in 0..10 {
+                    let presses =
+                        [&[rotation, Press::DasLeft], &vec![Press::Right; col][..]].concat();
+                    let target = after(&board, block, &presses);
+                    let optimal = optimal_presses(&board, block, &target).unwrap();
+                    assert!(optimal.len() <= 3, "{block} at column {col}: {optimal:?}");
+                    assert_eq!(after(&board, block, &optimal), target);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn an_extra_tap_or_rotation_is_a_fault() {
+        let board = spawned(Block::T, 10);
+        let played: [&[Press]; 4] = [
+            &[Press::Left],
+            &[Press::Left, Press::Right, Press::Left],
+            &[Press::Cw, Press::Ccw, Press::Left],
+            &[Press::DasLeft, Press::Right, Press::Right, Press::Right],
+        ];
+        for presses in played {
+            let target = after(&board, Block::T, presses);
+            let optimal = optimal_presses(&board, Block::T, &target).unwrap();
+            assert_eq!(optimal, [Press::Left]);
+            let verdict = Verdict {
+                block: Block::T,
+                presses: presses.len() as u32,
+                optimal,
+            };
+            assert_eq!(verdict.is_fault(), presses.len() > 1, "{presses:?}");
+        }
+    }
+
+    #[test]
+    fn gives_up_past_max_presses() {
+        // in the middle of a wide board DAS is no shortcut, only taps
+        let board = spawned(Block::O, 30);
+        let taps = after(&board, Block::O, &[Press::Left; MAX_PRESSES]);
+        let optimal = optimal_presses(&board, Block::O, &taps).unwrap();
+        assert_eq!(optimal, [Press::Left; MAX_PRESSES]);
+        let one_more = after(&board, Block::O, &[Press::Left; MAX_PRESSES + 1]);
+        assert_eq!(optimal_presses(&board, Block::O, &one_more), None);
+    }
+}
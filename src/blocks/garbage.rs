@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+
+use crate::{schedule::InGameSet, GameState};
+
+use super::{
+    blocks::{
+        block_spawner, clear_line, BlockState, Board, BoardBlockState, LineClear, PREVIEW_COUNT,
+    },
+    rng::GameRng,
+};
+
+pub struct GarbagePlugin;
+
+impl Plugin for GarbagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingGarbage>().add_systems(
+            FixedUpdate,
+            apply_garbage
+                .in_set(InGameSet::BoardInitUpdate)
+                .after(clear_line)
+                .before(block_spawner::<PREVIEW_COUNT>),
+        );
+    }
+}
+
+/// Garbage lines received from an opponent, inserted before the next spawn.
+#[derive(Resource, Default, Clone)]
+pub struct PendingGarbage(pub usize);
+
+/// Lines of garbage sent to the opponent for clearing `lines` at once.
+pub const fn attack_for_lines(lines: usize) -> usize {
+    match lines {
+        0 | 1 => 0,
+        2 => 1,
+        3 => 2,
+        _ => 4,
+    }
+}
+
+fn apply_garbage(
+    state: Query<&BlockState>,
+    mut pending: ResMut<PendingGarbage>,
+    mut board: ResMut<Board>,
+    mut rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameState>>,
+    line_clear: Res<LineClear>,
+) {
+    if pending.0 == 0 || line_clear.is_clearing() || !state.iter().all(|&x| x == BlockState::Placed)
+    {
+        return;
+    }
+    let lines = std::mem::take(&mut pending.0);
+    let board = &mut board.inner;
+    let cols = board[0].len();
+    let hole = rng.garbage_hole(cols);
+
+    for _ in 0..lines {
+        // anything pushed out of the top is a top out
+        if board[0].iter().any(|x| x.is_placed()) {
+            next_state.set(GameState::GameOver);
+        }
+        board.remove(0);
+        board.push(
+            (0..cols)
+                .map(|col| {
+                    if col == hole {
+                        BoardBlockState::Empty
+                    } else {
+                        BoardBlockState::Garbage
+                    }
+                })
+                .collect(),
+        );
+    }
+}
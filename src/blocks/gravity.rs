@@ -3,14 +3,22 @@ use bevy::prelude::*;
 use crate::schedule::InGameSet;
 
 use super::{
-    blocks::{Block, BlockState, Board, BoardBlockState, Level},
-    movement::SpeedTimer,
+    blocks::{Block, BlockState, Board, BoardBlockState, EntryDelay, Level, Links},
+    handling::Handling,
+    input::{PlayerInput, TickInput},
+    movement::{block_movement_controls, SpeedTimer},
 };
 pub struct GravityPlugin;
 
 impl Plugin for GravityPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, block_gravity.in_set(InGameSet::EntityMovement));
+        // input first, so a move and a drop on the same tick always resolve the same way
+        app.add_systems(
+            FixedUpdate,
+            block_gravity
+                .in_set(InGameSet::EntityMovement)
+                .after(block_movement_controls),
+        );
     }
 }
 
@@ -18,67 +26,115 @@ fn block_gravity(
     mut query: Query<(&Block, &mut BlockState), With<Block>>,
     mut board_b: ResMut<Board>,
     level: Res<Level>,
-    time: Res<Time>,
-    keyboard_input: Res<Input<KeyCode>>,
+    input: Res<TickInput>,
+    handling: Res<Handling>,
     mut timer: ResMut<SpeedTimer>,
+    mut entry_delay: ResMut<EntryDelay>,
 ) {
     if let Some((block, mut state)) = query.iter_mut().find(|x| *x.1 == BlockState::Falling) {
-        timer.watch.tick(time.delta());
+        let piece = board_b.pieces + 1;
+        if input.just_pressed(PlayerInput::HARD_DROP) {
+            timer.frames = 0;
+            if hard_drop(&mut board_b.inner, *block, piece) {
+                *state = BlockState::Placed;
+                board_b.pieces = piece;
+                entry_delay.frames = handling.are_at(board_b.height_of(piece));
+            }
+            return;
+        }
 
-        let pressed = keyboard_input.pressed(KeyCode::Down) && level.0 <= 19;
+        timer.frames += 1;
 
-        if timer.watch.elapsed() >= level.get_duraiton()
-            || (pressed && timer.watch.elapsed() >= Level(19).get_duraiton())
-        {
-            let board = &mut board_b.inner;
-            timer.watch.reset();
-            let rows = board.len();
-            let cols = board[0].len();
+        let soft_drop = input.pressed(PlayerInput::SOFT_DROP);
+        if soft_drop && handling.soft_drop_factor == 0 {
+            sonic_drop(&mut board_b.inner, *block);
+        }
 
-            for x in board
-                .last_mut()
-                .unwrap()
-                .iter_mut()
-                .filter(|x| x.is_falling())
-            {
-                *x = BoardBlockState::Placed { block_type: *block };
+        let factor = if soft_drop {
+            handling.soft_drop_factor.max(1)
+        } else {
+            1
+        };
+        if timer.frames * factor >= level.gravity_frames() {
+            timer.frames = 0;
+            if drop_block(&mut board_b.inner, *block, piece) {
                 *state = BlockState::Placed;
+                board_b.pieces = piece;
+                entry_delay.frames = handling.are_at(board_b.height_of(piece));
             }
+        }
+    }
+}
+
+/// Moves the falling piece down as far as it goes, without locking it.
+fn sonic_drop(board: &mut [Vec<BoardBlockState>], block: Block) {
+    for _ in 0..board.len() {
+        let mut next = board.to_vec();
+        // a locked copy is thrown away, whatever it's numbered
+        if drop_block(&mut next, block, 0) {
+            return;
+        }
+        board.clone_from_slice(&next);
+    }
+}
 
-            // first pass, check if it's possible to move down
+/// Drops the falling piece all the way down and locks it as `piece`.
+/// Returns whether it locked.
+pub(super) fn hard_drop(board: &mut [Vec<BoardBlockState>], block: Block, piece: u32) -> bool {
+    (0..=board.len()).any(|_| drop_block(board, block, piece))
+}
 
-            let mut block_allowed_to_move = Vec::with_capacity(4);
-            for row in (1..rows).rev() {
-                for col in 0..cols {
-                    // the current dot if moving
-                    if board[row - 1][col].is_falling() {
-                        // previous dot status
-                        match board[row][col] {
-                            BoardBlockState::Empty | BoardBlockState::Falling { .. } => {
-                                block_allowed_to_move.push(true);
-                            }
-                            BoardBlockState::Placed { .. } => {
-                                block_allowed_to_move.push(false);
-                            }
-                        }
-                    }
-                }
-            }
-            // second pass, move it down without checking
-            for row in (1..rows).rev() {
-                for col in 0..cols {
-                    // Apply gravity from bottom to second row (since the first row can't move down)
-                    if matches!(board[row][col], BoardBlockState::Falling { .. }) {
-                        if block_allowed_to_move.iter().all(|&x| x) {
-                            board[row][col] = BoardBlockState::Empty;
-                            board[row + 1][col] = BoardBlockState::Falling { block_type: *block };
-                        } else {
-                            board[row][col] = BoardBlockState::Placed { block_type: *block };
-                            *state = BlockState::Placed;
-                        }
-                    }
-                }
-            }
+/// Moves the falling piece down one row, locking it in place as `piece` if
+/// it can't. Returns whether the piece locked.
+pub fn drop_block(board: &mut [Vec<BoardBlockState>], block: Block, piece: u32) -> bool {
+    let rows = board.len();
+    // links first, they're worked out from the falling cells
+    let falling = board
+        .iter()
+        .enumerate()
+        .flat_map(|(row, cells)| {
+            cells
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| x.is_falling())
+                .map(move |(col, _)| (row, col))
+        })
+        .map(|(row, col)| ((row, col), Links::at(board, row, col)))
+        .collect::<Vec<_>>();
+    let lock = |board: &mut [Vec<BoardBlockState>], cells: &[((usize, usize), Links)]| {
+        for &((row, col), links) in cells {
+            board[row][col] = BoardBlockState::Placed {
+                block_type: block,
+                piece,
+                links,
+            };
+        }
+    };
+
+    // whatever reached the bottom locks there, the rest stops on it like on
+    // anything placed (only one a rotation cut apart keeps falling)
+    let (bottom, rest): (Vec<_>, Vec<_>) = falling
+        .into_iter()
+        .partition(|&((row, _), _)| row + 1 == rows);
+    lock(board, &bottom);
+    let stopped = rest
+        .iter()
+        .any(|&((row, col), _)| board[row + 1][col].is_placed());
+
+    // cells in the top row have never been moved or locked, replays depend on it
+    let rest = rest
+        .into_iter()
+        .filter(|&((row, _), _)| row > 0)
+        .collect::<Vec<_>>();
+    if stopped {
+        lock(board, &rest);
+    } else {
+        for &((row, col), _) in &rest {
+            board[row][col] = BoardBlockState::Empty;
+        }
+        for &((row, col), _) in &rest {
+            board[row + 1][col] = BoardBlockState::Falling { block_type: block };
         }
     }
+    !bottom.is_empty() || stopped
 }
@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How the piece responds to held buttons and how long the game waits
+/// between pieces, all counted in frames.
+///
+/// This changes how a game plays out, so replays and versus opponents
+/// carry the handling they were played with.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Handling {
+    /// Delayed auto shift: frames a direction has to be held before it
+    /// starts repeating.
+    pub das: u32,
+    /// Auto repeat rate: frames between repeated moves once DAS kicked in,
+    /// `0` moves all the way to the wall at once.
+    pub arr: u32,
+    /// How many times faster than gravity soft drop falls, `0` drops to the
+    /// floor at once (without locking).
+    pub soft_drop_factor: u32,
+    /// DAS cut delay: frames auto repeat is held off after a rotation.
+    pub das_cut_delay: u32,
+    /// Frames full rows stay on the board before collapsing, with nothing
+    /// falling meanwhile.
+    pub line_clear_delay: u32,
+    /// Entry delay: frames after a piece locks, and its lines are cleared,
+    /// before the next one spawns.
+    pub are: u32,
+    /// Work ARE out from how high the piece locked like the NES instead, 10
+    /// frames in the bottom two rows and 2 more every 4 rows up to 18.
+    pub nes_are: bool,
+    /// A direction held while no piece is falling keeps charging DAS, so
+    /// the next one can shift at once. Otherwise it starts over.
+    pub are_das_charge: bool,
+    /// Rotations and hold pressed while no piece is falling are done as the
+    /// next one spawns (IRS and IHS).
+    pub initial_actions: bool,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Self {
+            das: 9,
+            arr: 3,
+            soft_drop_factor: 6,
+            das_cut_delay: 0,
+            line_clear_delay: 0,
+            are: 0,
+            nes_are: false,
+            are_das_charge: false,
+            initial_actions: false,
+        }
+    }
+}
+
+impl Handling {
+    /// Space separated fields, for the replay and versus line formats.
+    pub fn encode(&self) -> String {
+        format!(
+            "{} {} {} {} {} {} {} {} {}",
+            self.das,
+            self.arr,
+            self.soft_drop_factor,
+            self.das_cut_delay,
+            self.line_clear_delay,
+            self.are,
+            u32::from(self.nes_are),
+            u32::from(self.are_das_charge),
+            u32::from(self.initial_actions),
+        )
+    }
+
+    /// Fields added later may be missing, from older replays, and are then
+    /// `old`. Switches are `0` or `1`.
+    pub fn decode(text: &str) -> Option<Self> {
+        let mut fields = text.split(' ').map(str::parse);
+        let mut next = |old| fields.next().map_or(old, Result::ok);
+        Some(Self {
+            das: next(None)?,
+            arr: next(None)?,
+            soft_drop_factor: next(None)?,
+            das_cut_delay: next(None)?,
+            line_clear_delay: next(Some(0))?,
+            are: next(Some(0))?,
+            nes_are: next(Some(0))? != 0,
+            are_das_charge: next(Some(0))? != 0,
+            initial_actions: next(Some(0))? != 0,
+        })
+    }
+
+    /// ARE after a piece locked with `height` rows under it.
+    pub fn are_at(&self, height: usize) -> u32 {
+        if !self.nes_are {
+            return self.are;
+        }
+        (10 + 2 * ((height as u32 + 2) / 4)).min(18)
+    }
+}
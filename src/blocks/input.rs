@@ -0,0 +1,327 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+/// Everything a player can do, whichever key or button is bound to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    Hold,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Self; 9] = [
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::SoftDrop,
+        Self::HardDrop,
+        Self::RotateCw,
+        Self::RotateCcw,
+        Self::Rotate180,
+        Self::Hold,
+        Self::Pause,
+    ];
+
+    /// The simulation button this presses, `None` for actions outside of
+    /// gameplay.
+    pub const fn button(self) -> Option<PlayerInput> {
+        match self {
+            Self::MoveLeft => Some(PlayerInput::LEFT),
+            Self::MoveRight => Some(PlayerInput::RIGHT),
+            Self::SoftDrop => Some(PlayerInput::SOFT_DROP),
+            Self::HardDrop => Some(PlayerInput::HARD_DROP),
+            Self::RotateCw => Some(PlayerInput::ROTATE_CW),
+            Self::RotateCcw => Some(PlayerInput::ROTATE_CCW),
+            Self::Rotate180 => Some(PlayerInput::ROTATE_180),
+            Self::Hold => Some(PlayerInput::HOLD),
+            Self::Pause => None,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::MoveLeft => "Move left",
+            Self::MoveRight => "Move right",
+            Self::SoftDrop => "Soft drop",
+            Self::HardDrop => "Hard drop",
+            Self::RotateCw => "Rotate clockwise",
+            Self::RotateCcw => "Rotate counterclockwise",
+            Self::Rotate180 => "Rotate 180",
+            Self::Hold => "Hold",
+            Self::Pause => "Pause",
+        }
+    }
+}
+
+/// Keys bound to each action, any of them triggers it.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<Action, Vec<KeyCode>>")]
+pub struct KeyBindings(pub BTreeMap<Action, Vec<KeyCode>>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self(BTreeMap::from([
+            (Action::MoveLeft, vec![KeyCode::Left]),
+            (Action::MoveRight, vec![KeyCode::Right]),
+            (Action::SoftDrop, vec![KeyCode::Down]),
+            (Action::HardDrop, vec![KeyCode::Space]),
+            (Action::RotateCw, vec![KeyCode::Z, KeyCode::Up]),
+            (Action::RotateCcw, vec![KeyCode::X]),
+            (Action::Rotate180, vec![KeyCode::A]),
+            (Action::Hold, vec![KeyCode::C, KeyCode::ShiftLeft]),
+            (Action::Pause, vec![KeyCode::Escape, KeyCode::P, KeyCode::Return]),
+        ]))
+    }
+}
+
+/// Actions missing from saved bindings (e.g. added since) get their
+/// default keys.
+impl From<BTreeMap<Action, Vec<KeyCode>>> for KeyBindings {
+    fn from(mut bindings: BTreeMap<Action, Vec<KeyCode>>) -> Self {
+        for (action, keys) in Self::default().0 {
+            bindings.entry(action).or_insert(keys);
+        }
+        Self(bindings)
+    }
+}
+
+impl KeyBindings {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn pressed(&self, keyboard: &Input<KeyCode>, action: Action) -> bool {
+        keyboard.any_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn just_pressed(&self, keyboard: &Input<KeyCode>, action: Action) -> bool {
+        keyboard.any_just_pressed(self.keys(action).iter().copied())
+    }
+}
+
+/// Controller buttons bound to each action, plus how far the left stick has
+/// to be pushed to count as a direction.
+///
+/// Directions go through the same auto shift as the keyboard, whether they
+/// come from the D-pad or the stick.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadBindings {
+    pub buttons: BTreeMap<Action, Vec<GamepadButtonType>>,
+    /// Stick deflection (0 to 1) below which it is ignored.
+    pub stick_deadzone: f32,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        use GamepadButtonType::*;
+        Self {
+            buttons: BTreeMap::from([
+                (Action::MoveLeft, vec![DPadLeft]),
+                (Action::MoveRight, vec![DPadRight]),
+                (Action::SoftDrop, vec![DPadDown]),
+                (Action::HardDrop, vec![DPadUp]),
+                (Action::RotateCw, vec![East]),
+                (Action::RotateCcw, vec![South]),
+                (Action::Rotate180, vec![North]),
+                (Action::Hold, vec![LeftTrigger, RightTrigger]),
+                (Action::Pause, vec![Start]),
+            ]),
+            stick_deadzone: 0.5,
+        }
+    }
+}
+
+impl GamepadBindings {
+    pub fn buttons(&self, action: Action) -> &[GamepadButtonType] {
+        self.buttons.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Direction the left stick points in, if it is pushed past the
+    /// deadzone. Diagonals go to whichever axis is pushed further.
+    fn stick(&self, axes: &Axis<GamepadAxis>, gamepad: Gamepad) -> Option<Action> {
+        let axis = |axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or_default()
+        };
+        let x = axis(GamepadAxisType::LeftStickX);
+        let y = axis(GamepadAxisType::LeftStickY);
+        if x.abs().max(y.abs()) < self.stick_deadzone {
+            None
+        } else if x.abs() >= y.abs() {
+            Some(if x < 0. {
+                Action::MoveLeft
+            } else {
+                Action::MoveRight
+            })
+        } else {
+            // up is left to the D-pad, a hard drop from a slipped stick
+            // would be too costly
+            (y < 0.).then_some(Action::SoftDrop)
+        }
+    }
+}
+
+/// Buttons pressed through the on-screen touch controls.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct TouchButtons {
+    /// On-screen buttons held down.
+    pub held: PlayerInput,
+    /// The pause button was touched this frame.
+    pub pause: bool,
+    /// Gestures not played yet, one tick each.
+    taps: VecDeque<PlayerInput>,
+    /// Gesture of the current tick.
+    tapped: PlayerInput,
+}
+
+impl TouchButtons {
+    /// Presses `button` for a single tick. A released tick follows, so taps
+    /// in a row all count as separate presses.
+    pub fn tap(&mut self, button: PlayerInput) {
+        self.taps.push_back(button);
+        self.taps.push_back(PlayerInput::default());
+    }
+
+    pub(crate) const fn current(&self) -> PlayerInput {
+        PlayerInput(self.held.0 | self.tapped.0)
+    }
+}
+
+pub(crate) fn step_touch(mut touch: ResMut<TouchButtons>) {
+    touch.tapped = touch.taps.pop_front().unwrap_or_default();
+}
+
+/// Keyboard, controllers and touch controls, read through the bindings.
+///
+/// There's a single local player, every device plays for them.
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    keyboard: Res<'w, Input<KeyCode>>,
+    keys: Res<'w, KeyBindings>,
+    buttons: Res<'w, Input<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    pad: Res<'w, GamepadBindings>,
+    gamepads: Res<'w, Gamepads>,
+    touch: Res<'w, TouchButtons>,
+}
+
+impl ActionInput<'_> {
+    fn pad_buttons(
+        &self,
+        gamepad: Gamepad,
+        action: Action,
+    ) -> impl Iterator<Item = GamepadButton> + '_ {
+        self.pad
+            .buttons(action)
+            .iter()
+            .map(move |&button_type| GamepadButton::new(gamepad, button_type))
+    }
+
+    /// Buttons held right now, on any device.
+    pub fn current(&self) -> PlayerInput {
+        let input = PlayerInput::from_actions(|action| {
+            self.keys.pressed(&self.keyboard, action)
+                || self.gamepads.iter().any(|x| {
+                    self.pad.stick(&self.axes, x) == Some(action)
+                        || self.buttons.any_pressed(self.pad_buttons(x, action))
+                })
+        });
+        PlayerInput(input.0 | self.touch.current().0)
+    }
+
+    /// Whether `action` was just pressed, on any device.
+    pub fn just_pressed(&self, action: Action) -> bool {
+        (action == Action::Pause && self.touch.pause)
+            || self.keys.just_pressed(&self.keyboard, action)
+            || self
+                .gamepads
+                .iter()
+                .any(|x| self.buttons.any_just_pressed(self.pad_buttons(x, action)))
+    }
+}
+
+/// Buttons held during a single simulation tick.
+///
+/// Gameplay only ever looks at this instead of the keyboard, so a tick can
+/// be replayed from recorded or remote input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PlayerInput(pub u8);
+
+impl PlayerInput {
+    pub const LEFT: Self = Self(1 << 0);
+    pub const RIGHT: Self = Self(1 << 1);
+    pub const SOFT_DROP: Self = Self(1 << 2);
+    pub const ROTATE_CW: Self = Self(1 << 3);
+    pub const ROTATE_CCW: Self = Self(1 << 4);
+    pub const HARD_DROP: Self = Self(1 << 5);
+    pub const ROTATE_180: Self = Self(1 << 6);
+    pub const HOLD: Self = Self(1 << 7);
+
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    fn from_actions(mut pressed: impl FnMut(Action) -> bool) -> Self {
+        Action::ALL
+            .into_iter()
+            .filter(|&action| pressed(action))
+            .filter_map(Action::button)
+            .fold(Self::default(), |acc, button| Self(acc.0 | button.0))
+    }
+}
+
+/// Input of the current tick, along with the previous one for edge detection.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct TickInput {
+    current: PlayerInput,
+    previous: PlayerInput,
+}
+
+impl TickInput {
+    pub fn advance(&mut self, input: PlayerInput) {
+        self.previous = self.current;
+        self.current = input;
+    }
+
+    pub const fn current(&self) -> PlayerInput {
+        self.current
+    }
+
+    pub const fn pressed(&self, button: PlayerInput) -> bool {
+        self.current.contains(button)
+    }
+
+    pub const fn just_pressed(&self, button: PlayerInput) -> bool {
+        self.current.contains(button) && !self.previous.contains(button)
+    }
+
+    /// Number of buttons pressed down on this tick.
+    pub const fn presses(&self) -> u32 {
+        (self.current.0 & !self.previous.0).count_ones()
+    }
+}
+
+/// Where [`TickInput`] comes from each tick.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputSource {
+    /// The keyboard, controllers or touch controls, see [`ActionInput`].
+    #[default]
+    Keyboard,
+    /// Something else (e.g. a bot) fills in [`TickInput`] during
+    /// `InGameSet::UserInput`.
+    External,
+}
+
+pub(super) fn sample_input(actions: ActionInput, mut input: ResMut<TickInput>) {
+    input.advance(actions.current());
+}
@@ -1,5 +1,14 @@
 pub mod blocks;
 mod definition;
-mod drawer;
-mod gravity;
-mod movement;
+pub mod drawer;
+pub mod finesse;
+pub mod garbage;
+pub mod gravity;
+pub mod handling;
+pub mod input;
+pub mod mode;
+pub mod movement;
+pub mod rng;
+pub mod skin;
+pub mod snapshot;
+pub mod stats;
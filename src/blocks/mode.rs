@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    schedule::{InGameSet, TICK_RATE},
+    GameState,
+};
+
+use super::blocks::{level_up, Lines};
+
+/// Lines to clear in [`GameMode::Sprint`].
+pub const SPRINT_LINES: usize = 40;
+/// Length of a [`GameMode::Ultra`] game: two minutes at the nominal
+/// [`TICK_RATE`].
+pub const ULTRA_FRAMES: u32 = 2 * 60 * TICK_RATE as u32;
+
+/// What ends a game, and what its result is ranked by.
+#[derive(
+    Resource,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    /// Endless, ranked by score.
+    #[default]
+    Marathon,
+    /// Clear [`SPRINT_LINES`] lines as fast as possible.
+    Sprint,
+    /// Score as much as possible in [`ULTRA_FRAMES`].
+    Ultra,
+    /// Against another player, until one tops out.
+    Versus,
+}
+
+impl GameMode {
+    /// Modes played alone, the ones with high scores.
+    pub const SOLO: [Self; 3] = [Self::Marathon, Self::Sprint, Self::Ultra];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Marathon => "Marathon",
+            Self::Sprint => "Sprint",
+            Self::Ultra => "Ultra",
+            Self::Versus => "Versus",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Marathon, Self::Sprint, Self::Ultra, Self::Versus]
+            .into_iter()
+            .find(|x| x.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Frames played in the current game.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct GameClock {
+    pub frames: u32,
+}
+
+impl GameClock {
+    /// Time played, in seconds at the nominal [`TICK_RATE`] (like the NES,
+    /// a faster `--tick-rate` doesn't make anyone faster).
+    pub fn seconds(&self) -> f64 {
+        f64::from(self.frames) / TICK_RATE
+    }
+}
+
+/// Why the last game ended.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameEnd {
+    /// A new piece had no room to spawn.
+    #[default]
+    TopOut,
+    /// The mode's goal or time limit was reached.
+    Finished,
+    /// Started over, from the pause menu or by the versus lobby.
+    Restarted,
+    /// Left for the start menu from the pause menu.
+    Quit,
+}
+
+impl GameEnd {
+    /// The game was cut short, so there's no result to show or keep.
+    pub const fn abandoned(self) -> bool {
+        matches!(self, Self::Restarted | Self::Quit)
+    }
+}
+
+pub struct ModePlugin;
+
+impl Plugin for ModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .init_resource::<GameClock>()
+            .init_resource::<GameEnd>()
+            .add_systems(
+                FixedUpdate,
+                (tick_clock, finish_mode)
+                    .chain()
+                    .after(level_up)
+                    .in_set(InGameSet::InfoUpdate),
+            );
+    }
+}
+
+fn tick_clock(mut clock: ResMut<GameClock>) {
+    clock.frames += 1;
+}
+
+fn finish_mode(
+    mode: Res<GameMode>,
+    clock: Res<GameClock>,
+    lines: Res<Lines>,
+    mut end: ResMut<GameEnd>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let finished = match *mode {
+        GameMode::Sprint => lines.total() >= SPRINT_LINES,
+        GameMode::Ultra => clock.frames >= ULTRA_FRAMES,
+        GameMode::Marathon | GameMode::Versus => false,
+    };
+    if finished {
+        *end = GameEnd::Finished;
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// `m:ss.cc` for a number of frames at the nominal [`TICK_RATE`].
+pub fn format_time(frames: u32) -> String {
+    let centis = u64::from(frames) * 100 / TICK_RATE as u64;
+    format!(
+        "{}:{:02}.{:02}",
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
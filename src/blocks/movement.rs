@@ -1,10 +1,12 @@
-use std::time::Duration;
-
-use bevy::{prelude::*, time::Stopwatch};
+use bevy::prelude::*;
 
 use crate::schedule::InGameSet;
 
-use super::blocks::{Block, BlockState, Board, BoardBlockState};
+use super::{
+    blocks::{Block, BlockState, Board, BoardBlockState, EntryDelay},
+    handling::Handling,
+    input::{PlayerInput, TickInput},
+};
 
 pub struct MovementPlugin;
 
@@ -13,28 +15,69 @@ impl Plugin for MovementPlugin {
         app.init_resource::<SpeedTimer>()
             .init_resource::<DasTimer>()
             .add_systems(
-                Update,
+                FixedUpdate,
                 block_movement_controls.in_set(InGameSet::EntityMovement),
             );
     }
 }
-#[derive(Resource, Default)]
+/// Frames since the falling piece last dropped a row.
+#[derive(Resource, Default, Clone)]
 pub struct SpeedTimer {
-    pub watch: Stopwatch,
+    pub frames: u32,
 }
 
-#[derive(Resource, Default)]
+/// Auto shift state, carried over between pieces.
+#[derive(Resource, Default, Clone)]
 pub struct DasTimer {
-    das: Stopwatch,
-    speed: Stopwatch,
+    /// Direction being shifted in, the last one pressed if both are held.
+    direction: Option<PlayerInput>,
+    /// Frames `direction` has been held.
+    charge: u32,
+    /// Frames since the last repeated move, `0` until the first one.
+    repeat: u32,
+    /// Frames left before auto repeat may resume after a rotation.
+    cut: u32,
 }
 
-fn block_movement_controls(
+impl DasTimer {
+    /// Direction to shift in, `None` if neither is held.
+    fn held(&self, input: &TickInput) -> Option<PlayerInput> {
+        let left = input.pressed(PlayerInput::LEFT);
+        let right = input.pressed(PlayerInput::RIGHT);
+        match (left, right) {
+            (true, true) if input.just_pressed(PlayerInput::RIGHT) => Some(PlayerInput::RIGHT),
+            (true, true) if input.just_pressed(PlayerInput::LEFT) => Some(PlayerInput::LEFT),
+            (true, true) => Some(self.direction.unwrap_or(PlayerInput::LEFT)),
+            (true, false) => Some(PlayerInput::LEFT),
+            (false, true) => Some(PlayerInput::RIGHT),
+            (false, false) => None,
+        }
+    }
+
+    /// A tick with nothing falling: the held direction charges up to DAS
+    /// with [`Handling::are_das_charge`], or else starts charging over.
+    /// Either way, a direction held on to isn't pressed anew on the next
+    /// piece.
+    fn wait(&mut self, input: &TickInput, handling: &Handling) {
+        self.cut = self.cut.saturating_sub(1);
+        let direction = self.held(input);
+        if direction != self.direction || !handling.are_das_charge {
+            self.charge = 0;
+            self.repeat = 0;
+        } else if direction.is_some() {
+            self.charge = (self.charge + 1).min(handling.das);
+        }
+        self.direction = direction;
+    }
+}
+
+pub(super) fn block_movement_controls(
     query: Query<(&Block, &BlockState), With<Block>>,
     mut board: ResMut<Board>,
-    keyboard_input: Res<Input<KeyCode>>,
-    time: Res<Time>,
+    input: Res<TickInput>,
+    handling: Res<Handling>,
     mut timer: ResMut<DasTimer>,
+    mut entry_delay: ResMut<EntryDelay>,
 ) {
     let board = &mut board.inner;
 
@@ -42,114 +85,143 @@ fn block_movement_controls(
         .iter()
         .find(|(_, state)| **state == BlockState::Falling)
     else {
+        timer.wait(&input, &handling);
         return;
     };
+    // rotations saved up while waiting for the piece
+    let buffered = std::mem::take(&mut entry_delay.buffered);
+    let rotate = |button| input.just_pressed(button) || buffered.contains(button);
 
-    let mut das_handler = |keycode, translator: &mut dyn FnMut()| {
-        timer.das.tick(time.delta());
-        if keyboard_input.just_pressed(keycode) {
-            translator();
-        }
-        if timer.das.elapsed() >= Duration::from_millis(150) {
-            timer.speed.tick(time.delta());
-            if keyboard_input.just_released(keycode) {
-                timer.das.reset();
-            }
-            if timer.speed.elapsed() >= Duration::from_millis(50) {
-                timer.speed.reset();
-                translator();
-            }
+    timer.cut = timer.cut.saturating_sub(1);
+    if rotate(PlayerInput::ROTATE_CW) && block != &Block::O {
+        rotate_block(board, block, true);
+        timer.cut = handling.das_cut_delay;
+    }
+    if rotate(PlayerInput::ROTATE_CCW) && block != &Block::O {
+        rotate_block(board, block, false);
+        timer.cut = handling.das_cut_delay;
+    }
+    if rotate(PlayerInput::ROTATE_180) && block != &Block::O {
+        rotate_block(board, block, true);
+        rotate_block(board, block, true);
+        timer.cut = handling.das_cut_delay;
+    }
+
+    let Some(direction) = timer.held(&input) else {
+        timer.direction = None;
+        return;
+    };
+    let shift = |board: &mut [Vec<BoardBlockState>]| {
+        if direction == PlayerInput::LEFT {
+            move_left(board, *block);
+        } else {
+            move_right(board, *block);
         }
     };
 
-    if keyboard_input.any_just_pressed([KeyCode::Z, KeyCode::Up]) && block != &Block::O {
-        rotate_block(board, block, true);
+    if timer.direction != Some(direction) {
+        // newly pressed, or the other direction got released
+        timer.direction = Some(direction);
+        timer.charge = 0;
+        timer.repeat = 0;
+        shift(board);
+        return;
     }
-    if keyboard_input.just_pressed(KeyCode::X) && block != &Block::O {
-        rotate_block(board, block, false);
+    timer.charge += 1;
+    if timer.charge < handling.das || timer.cut > 0 {
+        return;
+    }
+    if handling.arr == 0 {
+        for _ in 0..board[0].len() {
+            shift(board);
+        }
+        return;
     }
+    // the first repeat is right at DAS, then one every ARR frames
+    if timer.repeat == 0 || timer.repeat >= handling.arr {
+        timer.repeat = 0;
+        shift(board);
+    }
+    timer.repeat += 1;
+}
+
+pub fn move_left(board: &mut [Vec<BoardBlockState>], block: Block) {
+    let mut block_allowed_to_move = Vec::with_capacity(4);
+
+    let rows = board.len();
+    let cols = board[0].len();
+
+    let at_edge = board.iter().map(|x| x[0]).any(BoardBlockState::is_falling);
 
-    if keyboard_input.pressed(KeyCode::Left) {
-        das_handler(KeyCode::Left, &mut || {
-            let mut block_allowed_to_move = Vec::with_capacity(4);
-
-            let rows = board.len();
-            let cols = board[0].len();
-
-            let at_edge = board.iter().map(|x| x[0]).any(BoardBlockState::is_falling);
-
-            for col in 0..cols - 1 {
-                for row in 0..rows {
-                    if board[row][col + 1].is_falling() {
-                        match board[row][col] {
-                            BoardBlockState::Empty | BoardBlockState::Falling { .. } => {
-                                block_allowed_to_move.push(true);
-                            }
-                            BoardBlockState::Placed { .. } => {
-                                block_allowed_to_move.push(false);
-                            }
-                        }
+    for col in 0..cols - 1 {
+        for row in 0..rows {
+            if board[row][col + 1].is_falling() {
+                match board[row][col] {
+                    BoardBlockState::Empty | BoardBlockState::Falling { .. } => {
+                        block_allowed_to_move.push(true);
+                    }
+                    BoardBlockState::Placed { .. } | BoardBlockState::Garbage => {
+                        block_allowed_to_move.push(false);
                     }
                 }
             }
+        }
+    }
 
-            // second pass, move it down without checking
-            for col in 1..cols {
-                for row in 0..rows {
-                    if matches!(board[row][col], BoardBlockState::Falling { .. })
-                        && block_allowed_to_move.iter().all(|&x| x)
-                        && !at_edge
-                    {
-                        board[row][col] = BoardBlockState::Empty;
-                        board[row][col - 1] = BoardBlockState::Falling { block_type: *block };
-                    }
-                }
+    // second pass, move it down without checking
+    for col in 1..cols {
+        for row in 0..rows {
+            if matches!(board[row][col], BoardBlockState::Falling { .. })
+                && block_allowed_to_move.iter().all(|&x| x)
+                && !at_edge
+            {
+                board[row][col] = BoardBlockState::Empty;
+                board[row][col - 1] = BoardBlockState::Falling { block_type: block };
             }
-        });
-    } else if keyboard_input.pressed(KeyCode::Right) {
-        das_handler(KeyCode::Right, &mut || {
-            let mut block_allowed_to_move = Vec::with_capacity(4);
-
-            let rows = board.len();
-            let cols = board[0].len();
-
-            let at_edge = board
-                .iter()
-                .map(|x| x[x.len() - 1])
-                .any(BoardBlockState::is_falling);
-
-            for col in (1..cols).rev() {
-                for row in 0..rows {
-                    if board[row][col - 1].is_falling() {
-                        match board[row][col] {
-                            BoardBlockState::Empty | BoardBlockState::Falling { .. } => {
-                                block_allowed_to_move.push(true);
-                            }
-                            BoardBlockState::Placed { .. } => {
-                                block_allowed_to_move.push(false);
-                            }
-                        }
+        }
+    }
+}
+
+pub fn move_right(board: &mut [Vec<BoardBlockState>], block: Block) {
+    let mut block_allowed_to_move = Vec::with_capacity(4);
+
+    let rows = board.len();
+    let cols = board[0].len();
+
+    let at_edge = board
+        .iter()
+        .map(|x| x[x.len() - 1])
+        .any(BoardBlockState::is_falling);
+
+    for col in (1..cols).rev() {
+        for row in 0..rows {
+            if board[row][col - 1].is_falling() {
+                match board[row][col] {
+                    BoardBlockState::Empty | BoardBlockState::Falling { .. } => {
+                        block_allowed_to_move.push(true);
+                    }
+                    BoardBlockState::Placed { .. } | BoardBlockState::Garbage => {
+                        block_allowed_to_move.push(false);
                     }
                 }
             }
+        }
+    }
 
-            // second pass, move it down without checking
-            for col in (0..cols).rev() {
-                for row in 0..rows {
-                    if matches!(board[row][col], BoardBlockState::Falling { .. })
-                        && block_allowed_to_move.iter().all(|&x| x)
-                        && !at_edge
-                    {
-                        board[row][col] = BoardBlockState::Empty;
-                        board[row][col + 1] = BoardBlockState::Falling { block_type: *block };
-                    }
-                }
+    // second pass, move it down without checking
+    for col in (0..cols).rev() {
+        for row in 0..rows {
+            if matches!(board[row][col], BoardBlockState::Falling { .. })
+                && block_allowed_to_move.iter().all(|&x| x)
+                && !at_edge
+            {
+                board[row][col] = BoardBlockState::Empty;
+                board[row][col + 1] = BoardBlockState::Falling { block_type: block };
             }
-        });
-    } else {
-        timer.das.reset();
+        }
     }
 }
+
 fn extract_matrix(
     board: &Vec<Vec<BoardBlockState>>,
     top_left: (usize, usize),
@@ -228,7 +300,7 @@ fn rotate_matrix(matrix: Vec<Vec<BoardBlockState>>) -> Vec<Vec<BoardBlockState>>
     new_piece
 }
 
-fn rotate_block(board: &mut Vec<Vec<BoardBlockState>>, block: &Block, clockwise: bool) {
+pub fn rotate_block(board: &mut Vec<Vec<BoardBlockState>>, block: &Block, clockwise: bool) {
     let rows = board.len();
     let cols = board[0].len();
     let mut vec = Vec::new();
@@ -266,3 +338,112 @@ fn rotate_block(board: &mut Vec<Vec<BoardBlockState>>, block: &Block, clockwise:
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn world(handling: Handling) -> World {
+        let mut world = World::new();
+        world.init_resource::<Board>();
+        world.init_resource::<TickInput>();
+        world.init_resource::<DasTimer>();
+        world.init_resource::<EntryDelay>();
+        world.insert_resource(handling);
+        world
+    }
+
+    /// Puts a fresh O at the top, in columns 4 and 5.
+    fn spawn(world: &mut World) -> Entity {
+        let mut board = Board::default();
+        assert!(board.spawn_block(Block::O));
+        world.insert_resource(board);
+        world.spawn((Block::O, BlockState::Falling)).id()
+    }
+
+    fn column(world: &World) -> usize {
+        let board = &world.resource::<Board>().inner;
+        (0..board[0].len())
+            .find(|&col| board.iter().any(|row| row[col].is_falling()))
+            .unwrap()
+    }
+
+    /// Ticks on which the piece moved, holding `input` for `ticks` ticks.
+    fn moves(world: &mut World, input: PlayerInput, ticks: u32) -> Vec<u32> {
+        let mut moved = Vec::new();
+        for tick in 0..ticks {
+            let before = column(world);
+            world.resource_mut::<TickInput>().advance(input);
+            world.run_system_once(block_movement_controls);
+            if column(world) != before {
+                moved.push(tick);
+            }
+        }
+        moved
+    }
+
+    const HANDLING: Handling = Handling {
+        das: 10,
+        arr: 2,
+        soft_drop_factor: 6,
+        das_cut_delay: 0,
+        line_clear_delay: 0,
+        are: 0,
+        nes_are: false,
+        are_das_charge: false,
+        initial_actions: false,
+    };
+
+    #[test]
+    fn first_repeat_is_at_das_then_every_arr() {
+        let mut world = world(HANDLING);
+        spawn(&mut world);
+        // four columns to the wall
+        assert_eq!(moves(&mut world, PlayerInput::LEFT, 30), [0, 10, 12, 14]);
+
+        let mut world = self::world(Handling { arr: 1, ..HANDLING });
+        spawn(&mut world);
+        assert_eq!(moves(&mut world, PlayerInput::RIGHT, 30), [0, 10, 11, 12]);
+
+        let mut world = self::world(Handling { arr: 0, ..HANDLING });
+        spawn(&mut world);
+        assert_eq!(moves(&mut world, PlayerInput::LEFT, 30), [0, 10]);
+        assert_eq!(column(&world), 0);
+    }
+
+    #[test]
+    fn direction_held_through_are_is_not_a_new_press() {
+        let mut world = world(HANDLING);
+        let piece = spawn(&mut world);
+        moves(&mut world, PlayerInput::LEFT, 3);
+        world.despawn(piece);
+
+        // nothing falling for a while
+        for _ in 0..20 {
+            world.resource_mut::<TickInput>().advance(PlayerInput::LEFT);
+            world.run_system_once(block_movement_controls);
+        }
+        // charging again from the piece's first tick, not moved by it
+        spawn(&mut world);
+        assert_eq!(moves(&mut world, PlayerInput::LEFT, 20), [9, 11, 13, 15]);
+    }
+
+    #[test]
+    fn das_charged_during_are_shifts_at_once() {
+        let mut world = world(Handling {
+            are_das_charge: true,
+            ..HANDLING
+        });
+        let piece = spawn(&mut world);
+        moves(&mut world, PlayerInput::LEFT, 3);
+        world.despawn(piece);
+        for _ in 0..20 {
+            world.resource_mut::<TickInput>().advance(PlayerInput::LEFT);
+            world.run_system_once(block_movement_controls);
+        }
+        spawn(&mut world);
+        assert_eq!(moves(&mut world, PlayerInput::LEFT, 20), [0, 2, 4, 6]);
+    }
+}
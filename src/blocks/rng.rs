@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::blocks::{Block, CurrentBlockWithPreview, PREVIEW_COUNT};
+
+/// All gameplay randomness, derived from a single seed so two peers
+/// started with the same seed see the exact same game.
+///
+/// Pieces and garbage use separate streams, so receiving garbage never
+/// shifts the piece sequence away from the opponent's.
+#[derive(Resource, Clone)]
+pub struct GameRng {
+    seed: u64,
+    pieces: StdRng,
+    garbage: StdRng,
+    /// Nothing was drawn from this seed yet.
+    fresh: bool,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pieces: StdRng::seed_from_u64(seed),
+            garbage: StdRng::seed_from_u64(seed.rotate_left(32) ^ 0x9e37_79b9_7f4a_7c15),
+            fresh: true,
+        }
+    }
+
+    /// Moves on to a new seed for the next game, so every game can be
+    /// reproduced from its own seed. A seed nothing was drawn from yet
+    /// (e.g. one just handed out by the lobby) is kept as is.
+    pub fn next_game(&mut self) {
+        if !self.fresh {
+            *self = Self::new(self.pieces.gen());
+        }
+    }
+
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_block(&mut self) -> Block {
+        self.fresh = false;
+        *Block::ALL.choose(&mut self.pieces).unwrap()
+    }
+
+    pub fn garbage_hole(&mut self, cols: usize) -> usize {
+        self.fresh = false;
+        self.garbage.gen_range(0..cols)
+    }
+}
+
+/// Seeds are saved as text, TOML integers stop at `i64::MAX`. Numbers are
+/// still read, older files have the ones that fit as such.
+pub mod seed_text {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seed {
+        Number(u64),
+        Text(String),
+    }
+
+    impl Seed {
+        fn value<E: Error>(self) -> Result<u64, E> {
+            match self {
+                Self::Number(seed) => Ok(seed),
+                Self::Text(text) => text.parse().map_err(E::custom),
+            }
+        }
+    }
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(seed)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        Seed::deserialize(deserializer)?.value()
+    }
+
+    /// The same for a seed that may not be set.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        use super::Seed;
+
+        pub fn serialize<S: Serializer>(
+            seed: &Option<u64>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match seed {
+                Some(seed) => super::serialize(seed, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<u64>, D::Error> {
+            Option::<Seed>::deserialize(deserializer)?
+                .map(Seed::value)
+                .transpose()
+        }
+    }
+}
+
+/// Starts the upcoming game from `seed` instead, preview included, e.g. for
+/// `--seed` or a replay.
+pub fn use_seed(world: &mut World, seed: u64) {
+    let mut rng = GameRng::new(seed);
+    world.insert_resource(CurrentBlockWithPreview::<PREVIEW_COUNT>::new(&mut rng));
+    world.insert_resource(rng);
+}
@@ -0,0 +1,211 @@
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    sprite::Mesh2dHandle,
+    utils::HashMap,
+};
+
+use crate::settings::Settings;
+
+use super::{
+    blocks::{Block, BoardBlockState, Links},
+    drawer::Cell,
+};
+
+/// What a square is drawn as: a piece's, or garbage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mino {
+    Piece(Block),
+    Garbage,
+}
+
+impl Mino {
+    /// In the order of the tiles of a skin's sprite sheet.
+    const ALL: [Self; 8] = [
+        Self::Piece(Block::T),
+        Self::Piece(Block::J),
+        Self::Piece(Block::L),
+        Self::Piece(Block::I),
+        Self::Piece(Block::O),
+        Self::Piece(Block::S),
+        Self::Piece(Block::Z),
+        Self::Garbage,
+    ];
+
+    pub const fn of(state: BoardBlockState) -> Option<Self> {
+        match state {
+            BoardBlockState::Placed { block_type, .. }
+            | BoardBlockState::Falling { block_type } => Some(Self::Piece(block_type)),
+            BoardBlockState::Garbage => Some(Self::Garbage),
+            BoardBlockState::Empty => None,
+        }
+    }
+
+    /// The flat colour, without a skin.
+    pub const fn color(self) -> Color {
+        match self {
+            Self::Piece(block) => block.get_color(),
+            Self::Garbage => Color::GRAY,
+        }
+    }
+
+    fn tile(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|&x| x == self)
+            .unwrap_or_default()
+    }
+}
+
+/// Gap left on a side of a cell not linked to the rest of its piece, in cells.
+const GAP: f32 = 1. / 16.;
+
+/// The mesh and material each [`Mino`] is drawn with, for each way it can be
+/// linked to the rest of its piece. Made once per skin so drawing never adds
+/// assets.
+#[derive(Resource, Default)]
+pub struct BlockAssets {
+    looks: Looks,
+    /// `visuals.skin` asked for.
+    skin: Option<String>,
+    /// Its sprite sheet, the looks so far stay until it's in.
+    loading: Option<Handle<Image>>,
+    /// The looks aren't made for [`Self::skin`] yet.
+    stale: bool,
+}
+
+impl BlockAssets {
+    pub fn look(&self, mino: Mino, links: Links) -> (Mesh2dHandle, Handle<ColorMaterial>) {
+        let (mesh, material) = &self.looks[&(mino, links)];
+        (mesh.clone().into(), material.clone())
+    }
+}
+
+/// Draws the minos with the sprite sheet `assets/skins/<visuals.skin>.png`,
+/// a row of square tiles in [`Mino::ALL`] order, or in flat colours without
+/// one. Changing the setting swaps the skin of everything already drawn.
+pub struct SkinPlugin;
+
+impl Plugin for SkinPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BlockAssets>()
+            .add_systems(Startup, flat_looks)
+            .add_systems(Update, (load_skin, apply_skin).chain());
+    }
+}
+
+fn flat_looks(
+    mut assets: ResMut<BlockAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    assets.looks = flat(&mut meshes, &mut materials);
+}
+
+type Looks = HashMap<(Mino, Links), (Handle<Mesh>, Handle<ColorMaterial>)>;
+
+/// Every way a cell can be linked.
+fn all_links() -> impl Iterator<Item = Links> {
+    (0..=Links::ALL.0).map(Links)
+}
+
+fn flat(meshes: &mut Assets<Mesh>, materials: &mut Assets<ColorMaterial>) -> Looks {
+    let quads = all_links()
+        .map(|links| (links, meshes.add(cell_quad(None, links))))
+        .collect::<Vec<_>>();
+    let mut looks = Looks::default();
+    for mino in Mino::ALL {
+        let material = materials.add(ColorMaterial::from(mino.color()));
+        for (links, quad) in &quads {
+            looks.insert((mino, *links), (quad.clone(), material.clone()));
+        }
+    }
+    looks
+}
+
+fn sheet(
+    sheet: Handle<Image>,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) -> Looks {
+    let material = materials.add(ColorMaterial::from(sheet));
+    Mino::ALL
+        .into_iter()
+        .flat_map(|mino| all_links().map(move |links| (mino, links)))
+        .map(|(mino, links)| {
+            let quad = meshes.add(cell_quad(Some(mino.tile()), links));
+            ((mino, links), (quad, material.clone()))
+        })
+        .collect()
+}
+
+/// Starts loading the sheet when `visuals.skin` changes.
+fn load_skin(settings: Res<Settings>, server: Res<AssetServer>, mut assets: ResMut<BlockAssets>) {
+    if !settings.is_changed() || settings.visuals.skin == assets.skin {
+        return;
+    }
+    assets.skin = settings.visuals.skin.clone();
+    assets.loading = assets
+        .skin
+        .as_ref()
+        .map(|name| server.load(format!("skins/{name}.png")));
+    assets.stale = true;
+}
+
+/// Makes the looks once the sheet is in, falling back on the flat colours if
+/// there's no skin or it can't be loaded, and redraws everything with them.
+fn apply_skin(
+    server: Res<AssetServer>,
+    mut assets: ResMut<BlockAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut cells: Query<(&Cell, &mut Mesh2dHandle, &mut Handle<ColorMaterial>)>,
+) {
+    if !assets.stale {
+        return;
+    }
+    assets.looks = match assets.loading.clone() {
+        None => flat(&mut meshes, &mut materials),
+        Some(handle) => match server.get_load_state(&handle) {
+            Some(LoadState::Loaded) => sheet(handle, &mut meshes, &mut materials),
+            Some(LoadState::Failed) | None => {
+                warn!("Couldn't load skin {:?}, using flat colours", assets.skin);
+                flat(&mut meshes, &mut materials)
+            }
+            _ => return,
+        },
+    };
+    assets.loading = None;
+    assets.stale = false;
+    for (cell, mut mesh, mut material) in &mut cells {
+        if let Some((mino, links)) = cell.look() {
+            (*mesh, *material) = assets.look(mino, links);
+        }
+    }
+}
+
+/// A square with a [`GAP`] on the sides not in `links`, showing only `tile`
+/// of the sheet if there's one.
+fn cell_quad(tile: Option<usize>, links: Links) -> Mesh {
+    let gap = |side| if links.contains(side) { 0. } else { GAP };
+    let (left, right) = (-0.5 + gap(Links::LEFT), 0.5 - gap(Links::RIGHT));
+    let (bottom, top) = (-0.5 + gap(Links::DOWN), 0.5 - gap(Links::UP));
+    let corners = [[left, bottom], [right, bottom], [right, top], [left, top]];
+    let tiles = Mino::ALL.len() as f32;
+    let uv = |[x, y]: [f32; 2]| {
+        let (u, v) = (x + 0.5, 0.5 - y);
+        match tile {
+            Some(tile) => [(tile as f32 + u) / tiles, v],
+            None => [u, v],
+        }
+    };
+    Mesh::new(PrimitiveTopology::TriangleList)
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            corners.map(|[x, y]| [x, y, 0.]).to_vec(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0., 0., 1.]; 4])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, corners.map(uv).to_vec())
+        .with_indices(Some(Indices::U32(vec![0, 1, 2, 0, 2, 3])))
+}
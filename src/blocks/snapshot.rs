@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+
+use super::{
+    blocks::{
+        Block, BlockState, Board, CurrentBlockWithPreview, EntryDelay, Level, LineClear, Lines,
+        Score, PREVIEW_COUNT,
+    },
+    garbage::PendingGarbage,
+    input::TickInput,
+    mode::GameClock,
+    movement::{DasTimer, SpeedTimer},
+    rng::GameRng,
+};
+
+/// Everything [`TetrisSimulationPlugin`](super::blocks::TetrisSimulationPlugin)
+/// needs to continue a game from a given tick.
+#[derive(Clone)]
+pub struct GameSnapshot {
+    board: Board,
+    line_clear: LineClear,
+    entry_delay: EntryDelay,
+    preview: CurrentBlockWithPreview<PREVIEW_COUNT>,
+    level: Level,
+    lines: Lines,
+    score: Score,
+    clock: GameClock,
+    speed_timer: SpeedTimer,
+    das_timer: DasTimer,
+    rng: GameRng,
+    pending_garbage: PendingGarbage,
+    input: TickInput,
+    falling: Option<Block>,
+}
+
+impl GameSnapshot {
+    pub fn save(world: &mut World) -> Self {
+        let falling = world
+            .query::<(&Block, &BlockState)>()
+            .iter(world)
+            .find(|(_, state)| **state == BlockState::Falling)
+            .map(|(block, _)| *block);
+        Self {
+            board: world.resource::<Board>().clone(),
+            line_clear: world.resource::<LineClear>().clone(),
+            entry_delay: world.resource::<EntryDelay>().clone(),
+            preview: world
+                .resource::<CurrentBlockWithPreview<PREVIEW_COUNT>>()
+                .clone(),
+            level: world.resource::<Level>().clone(),
+            lines: world.resource::<Lines>().clone(),
+            score: world.resource::<Score>().clone(),
+            clock: world.resource::<GameClock>().clone(),
+            speed_timer: world.resource::<SpeedTimer>().clone(),
+            das_timer: world.resource::<DasTimer>().clone(),
+            rng: world.resource::<GameRng>().clone(),
+            pending_garbage: world.resource::<PendingGarbage>().clone(),
+            input: *world.resource::<TickInput>(),
+            falling,
+        }
+    }
+
+    pub fn restore(&self, world: &mut World) {
+        world.insert_resource(self.board.clone());
+        world.insert_resource(self.line_clear.clone());
+        world.insert_resource(self.entry_delay.clone());
+        world.insert_resource(self.preview.clone());
+        world.insert_resource(self.level.clone());
+        world.insert_resource(self.lines.clone());
+        world.insert_resource(self.score.clone());
+        world.insert_resource(self.clock.clone());
+        world.insert_resource(self.speed_timer.clone());
+        world.insert_resource(self.das_timer.clone());
+        world.insert_resource(self.rng.clone());
+        world.insert_resource(self.pending_garbage.clone());
+        world.insert_resource(self.input);
+
+        let blocks = world
+            .query_filtered::<Entity, With<Block>>()
+            .iter(world)
+            .collect::<Vec<_>>();
+        for entity in blocks {
+            world.despawn(entity);
+        }
+        if let Some(block) = self.falling {
+            world.spawn((block, BlockState::Falling));
+        }
+    }
+}
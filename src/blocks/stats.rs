@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    prelude::*,
+    sprite::{Anchor, MaterialMesh2dBundle},
+};
+
+use crate::{schedule::InGameSet, settings::Settings, GameState};
+
+use super::{
+    blocks::{
+        level_up, Block, LinesIncrementEvent, Links, PieceDealtEvent, PiecePlacedEvent, POINT_SIZE,
+    },
+    drawer::Cell,
+    garbage::attack_for_lines,
+    input::TickInput,
+    mode::{format_time, GameClock, GameMode},
+    skin::{BlockAssets, Mino},
+};
+
+/// How the local player is doing, counted from gameplay events and the
+/// ticks' input. Nothing in the game rules depends on these.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct Stats {
+    pub pieces: u32,
+    /// [`Self::pieces`] by kind.
+    pub placed: BTreeMap<Block, u32>,
+    /// Buttons pressed, each press of each button counts.
+    pub keys: u32,
+    /// Line clears by size: singles, doubles, triples and tetrises.
+    pub clears: [u32; 4],
+    /// Garbage the line clears send in versus, counted in every mode.
+    pub attack: usize,
+    /// Pieces placed with more presses than needed.
+    pub finesse_faults: u32,
+    /// Pieces out of the queue by kind, whether placed yet or not.
+    pub dealt: BTreeMap<Block, u32>,
+    /// Pieces dealt since the last I.
+    pub drought: u32,
+}
+
+impl Stats {
+    /// Pieces per second.
+    pub fn pps(&self, clock: &GameClock) -> f64 {
+        per_second(f64::from(self.pieces), clock)
+    }
+
+    /// Attack per minute.
+    pub fn apm(&self, clock: &GameClock) -> f64 {
+        per_second(self.attack as f64, clock) * 60.
+    }
+
+    /// Keys per piece.
+    pub fn kpp(&self) -> f64 {
+        if self.pieces == 0 {
+            0.
+        } else {
+            f64::from(self.keys) / f64::from(self.pieces)
+        }
+    }
+
+    /// Everything but the pieces by kind, a stat per line.
+    pub fn summary(&self, clock: &GameClock) -> String {
+        let [singles, doubles, triples, tetrises] = self.clears;
+        format!(
+            "time: {}\npieces: {}\npps: {:.2}\nkeys: {}\nkpp: {:.2}\napm: {:.1}\n\
+             singles: {singles}\ndoubles: {doubles}\ntriples: {triples}\ntetrises: {tetrises}\n\
+             finesse faults: {}",
+            format_time(clock.frames),
+            self.pieces,
+            self.pps(clock),
+            self.keys,
+            self.kpp(),
+            self.apm(clock),
+            self.finesse_faults,
+        )
+    }
+}
+
+fn per_second(count: f64, clock: &GameClock) -> f64 {
+    if clock.frames == 0 {
+        0.
+    } else {
+        count / clock.seconds()
+    }
+}
+
+/// Keeps [`Stats`] for the local game and shows them next to the board if
+/// `visuals.stats_panel` is on. `visuals.piece_panel` shows the pieces dealt
+/// by kind, like the statistics column of NES Tetris, except in versus where
+/// the opponent's board takes its place.
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Stats>()
+            .add_systems(
+                FixedUpdate,
+                (count_keys, count_pieces, count_dealt, count_clears)
+                    .after(level_up)
+                    .in_set(InGameSet::InfoUpdate),
+            )
+            // every frame, the time and PPS go on while the board stays
+            .add_systems(
+                Update,
+                stats_panel
+                    .run_if(in_state(GameState::InGame).or_else(in_state(GameState::GameOver)))
+                    .run_if(|settings: Res<Settings>| settings.visuals.stats_panel),
+            )
+            .add_systems(
+                Update,
+                piece_panel
+                    .run_if(|settings: Res<Settings>| settings.visuals.piece_panel)
+                    .run_if(not(resource_equals(GameMode::Versus)))
+                    .in_set(InGameSet::BoardDrawer),
+            )
+            .add_systems(
+                Update,
+                (
+                    despawn::<StatsText>
+                        .run_if(|settings: Res<Settings>| !settings.visuals.stats_panel),
+                    despawn::<PiecePanel>
+                        .run_if(|settings: Res<Settings>| !settings.visuals.piece_panel),
+                ),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                (reset_stats, despawn::<StatsText>, despawn::<PiecePanel>),
+            );
+    }
+}
+
+fn count_keys(input: Res<TickInput>, mut stats: ResMut<Stats>) {
+    stats.keys += input.presses();
+}
+
+fn count_pieces(mut placed: EventReader<PiecePlacedEvent>, mut stats: ResMut<Stats>) {
+    for PiecePlacedEvent(block) in placed.read() {
+        stats.pieces += 1;
+        *stats.placed.entry(*block).or_default() += 1;
+    }
+}
+
+fn count_dealt(mut dealt: EventReader<PieceDealtEvent>, mut stats: ResMut<Stats>) {
+    for PieceDealtEvent(block) in dealt.read() {
+        *stats.dealt.entry(*block).or_default() += 1;
+        stats.drought = if *block == Block::I {
+            0
+        } else {
+            stats.drought + 1
+        };
+    }
+}
+
+fn count_clears(mut lines: EventReader<LinesIncrementEvent>, mut stats: ResMut<Stats>) {
+    for &LinesIncrementEvent(lines) in lines.read() {
+        if lines == 0 {
+            continue;
+        }
+        stats.clears[lines.min(4) - 1] += 1;
+        stats.attack += attack_for_lines(lines);
+    }
+}
+
+fn reset_stats(mut stats: ResMut<Stats>) {
+    *stats = Stats::default();
+}
+
+/// Takes a panel down, once it's turned off or the game is over.
+fn despawn<T: Component>(mut commands: Commands, panel: Query<Entity, With<T>>) {
+    for entity in &panel {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[derive(Component)]
+struct StatsText;
+
+fn stats_panel(
+    stats: Res<Stats>,
+    clock: Res<GameClock>,
+    mut text: Query<&mut Text, With<StatsText>>,
+    mut commands: Commands,
+) {
+    let value = stats.summary(&clock);
+    let Ok(mut text) = text.get_single_mut() else {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    value,
+                    TextStyle {
+                        font_size: POINT_SIZE * 0.75,
+                        ..default()
+                    },
+                ),
+                text_anchor: Anchor::CenterRight,
+                transform: Transform::from_translation(Vec3::new(-POINT_SIZE * 6., 0., 0.)),
+                ..default()
+            },
+            StatsText,
+        ));
+        return;
+    };
+    // left alone once the game is over, rather than laid out every frame
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+/// Left edge of the piece panel.
+const PANEL_X: f32 = -POINT_SIZE * 19.;
+/// Height of a row of the piece panel.
+const PANEL_ROW: f32 = POINT_SIZE * 2.;
+
+/// Every part of the piece panel.
+#[derive(Component)]
+struct PiecePanel;
+
+#[derive(Component)]
+struct DealtText(Block);
+
+#[derive(Component)]
+struct DroughtText;
+
+fn piece_panel(
+    stats: Res<Stats>,
+    mut counts: Query<(&mut Text, &DealtText), Without<DroughtText>>,
+    mut drought: Query<&mut Text, With<DroughtText>>,
+    assets: Res<BlockAssets>,
+    mut commands: Commands,
+) {
+    if counts.is_empty() {
+        spawn_piece_panel(&mut commands, &assets);
+        return;
+    }
+    for (mut text, DealtText(block)) in &mut counts {
+        text.sections[0].value =
+            format!("{:03}", stats.dealt.get(block).copied().unwrap_or_default());
+    }
+    for mut text in &mut drought {
+        text.sections[0].value = format!("drought {}", stats.drought);
+    }
+}
+
+/// A half size render of each piece next to its count, and the I drought
+/// under them. The renders never change, only the counts are updated.
+fn spawn_piece_panel(commands: &mut Commands, assets: &BlockAssets) {
+    let cell = POINT_SIZE * 0.5;
+    let style = TextStyle {
+        font_size: POINT_SIZE * 0.75,
+        ..default()
+    };
+    for (index, block) in Block::ALL.into_iter().enumerate() {
+        let y = POINT_SIZE * 7. - PANEL_ROW * index as f32;
+        let mino = Mino::Piece(block);
+        // too small for gaps to show
+        let (mesh, material) = assets.look(mino, Links::ALL);
+        for (row, cells) in block.get_occupied().iter().enumerate() {
+            for (col, _) in cells.iter().enumerate().filter(|(_, x)| x.is_falling()) {
+                commands.spawn((
+                    MaterialMesh2dBundle {
+                        mesh: mesh.clone(),
+                        material: material.clone(),
+                        transform: Transform::from_scale(Vec3::splat(cell)).with_translation(
+                            Vec3::new(
+                                PANEL_X + cell * col as f32,
+                                y - cell * (row as f32 - 1.5),
+                                0.,
+                            ),
+                        ),
+                        ..default()
+                    },
+                    // so a new skin reaches it too
+                    Cell::new(mino, Links::ALL),
+                    PiecePanel,
+                ));
+            }
+        }
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section("", style.clone()),
+                text_anchor: Anchor::CenterLeft,
+                transform: Transform::from_translation(Vec3::new(
+                    PANEL_X + POINT_SIZE * 2.5,
+                    y,
+                    0.,
+                )),
+                ..default()
+            },
+            DealtText(block),
+            PiecePanel,
+        ));
+    }
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section("", style),
+            text_anchor: Anchor::CenterLeft,
+            transform: Transform::from_translation(Vec3::new(
+                PANEL_X - cell * 0.5,
+                POINT_SIZE * 7. - PANEL_ROW * Block::ALL.len() as f32,
+                0.,
+            )),
+            ..default()
+        },
+        DroughtText,
+        PiecePanel,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_text(app: &mut App) -> Vec<String> {
+        app.world
+            .query_filtered::<&Text, With<StatsText>>()
+            .iter(&app.world)
+            .map(|text| text.sections[0].value.clone())
+            .collect()
+    }
+
+    #[test]
+    fn stats_panel_keeps_time_and_goes_away() {
+        let mut settings = Settings::default();
+        settings.visuals.stats_panel = true;
+        let mut app = App::new();
+        app.add_state::<GameState>()
+            .insert_resource(State::new(GameState::InGame))
+            .insert_resource(settings)
+            .insert_resource(GameMode::default())
+            .init_resource::<GameClock>()
+            .add_plugins(StatsPlugin);
+
+        app.update();
+        app.update();
+        assert_eq!(stats_text(&mut app).len(), 1);
+
+        // nothing moved on the board, the time still goes on
+        app.world.resource_mut::<GameClock>().frames = 90;
+        app.update();
+        assert!(stats_text(&mut app)[0].contains("time: 0:01.50"));
+
+        app.world.resource_mut::<Settings>().visuals.stats_panel = false;
+        app.update();
+        assert!(stats_text(&mut app).is_empty());
+
+        app.world.resource_mut::<Settings>().visuals.stats_panel = true;
+        app.update();
+        app.update();
+        assert_eq!(stats_text(&mut app).len(), 1);
+        for state in [GameState::GameOver, GameState::StartMenu] {
+            app.world.resource_mut::<NextState<GameState>>().set(state);
+            app.update();
+        }
+        assert!(stats_text(&mut app).is_empty());
+    }
+}
@@ -12,6 +12,7 @@ impl Plugin for DrawBorderPlugin {
 #[derive(Component, Copy, Clone)]
 pub struct Border;
 
+/// The two rows share a line mesh, and so do the two columns.
 fn draw_borders(
     board: Res<Board>,
     mut commands: Commands,
@@ -20,12 +21,20 @@ fn draw_borders(
 ) {
     let border = Border;
     let board = &board.inner;
-    for row in [0, board.len() - 1] {
-        let mesh = Mesh::new(PrimitiveTopology::LineList).with_inserted_attribute(
+    let material = materials.add(ColorMaterial::from(Color::GRAY));
+    let horizontal = meshes.add(
+        Mesh::new(PrimitiveTopology::LineList).with_inserted_attribute(
             Mesh::ATTRIBUTE_POSITION,
             vec![[0., 0., 12.], [board[0].len() as f32, 0., 12.]],
-        );
-        let material = materials.add(ColorMaterial::from(Color::GRAY));
+        ),
+    );
+    let vertical = meshes.add(
+        Mesh::new(PrimitiveTopology::LineList).with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0., 0., 12.], [0., -((board.len() - 1) as f32), 12.]],
+        ),
+    );
+    for row in [0, board.len() - 1] {
         let transform = Transform::default()
             .with_scale(Vec3::from_array([POINT_SIZE, POINT_SIZE, -POINT_SIZE]))
             .with_translation(Vec3::from_array([
@@ -35,34 +44,26 @@ fn draw_borders(
             ]));
 
         let mesh_bundle = MaterialMesh2dBundle {
-            mesh: meshes.add(mesh).into(),
+            mesh: horizontal.clone().into(),
             transform,
-            material,
+            material: material.clone(),
             ..default()
         };
         commands.spawn((border, mesh_bundle));
     }
     for col in [0, board[0].len()] {
-        let material = materials.add(ColorMaterial::from(Color::GRAY));
         let transform = Transform::default()
             .with_scale(Vec3::from_array([POINT_SIZE, POINT_SIZE, -POINT_SIZE]))
             .with_translation(Vec3::from_array([
-                0. - POINT_SIZE * 4.5,
+                POINT_SIZE * col as f32 - POINT_SIZE * 4.5,
                 0. + POINT_SIZE * 9.5,
                 0.,
             ]));
 
-        let mesh = Mesh::new(PrimitiveTopology::LineList).with_inserted_attribute(
-            Mesh::ATTRIBUTE_POSITION,
-            vec![
-                [col as f32, 0., 12.],
-                [col as f32, -((board.len() - 1) as f32), 12.],
-            ],
-        );
         let mesh_bundle = MaterialMesh2dBundle {
-            mesh: meshes.add(mesh).into(),
+            mesh: vertical.clone().into(),
             transform,
-            material,
+            material: material.clone(),
             ..default()
         };
         commands.spawn((border, mesh_bundle));
@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    blocks::{
+        blocks::{Block, BlockState, Board, CurrentBlockWithPreview, PREVIEW_COUNT},
+        input::{InputSource, PlayerInput, TickInput},
+    },
+    schedule::InGameSet,
+};
+
+use self::search::{choose, path_to, placements, Move};
+
+pub mod search;
+
+/// Plays in place of the keyboard.
+///
+/// The bot picks a placement for each new piece and then presses the same
+/// buttons a player would through [`TickInput`], so it's bound by the real
+/// movement, DAS and gravity rules. The piece holding would bring in is
+/// weighed too, and held first when it does better.
+pub struct BotPlugin {
+    pub difficulty: BotDifficulty,
+}
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputSource::External)
+            .insert_resource(Bot {
+                difficulty: self.difficulty,
+                rng: StdRng::from_entropy(),
+                piece: None,
+                driver: PieceDriver::default(),
+            })
+            .add_systems(FixedUpdate, drive_bot.in_set(InGameSet::UserInput));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BotDifficulty {
+    /// Ticks to wait after each button press, and before reacting to a
+    /// new piece.
+    pub action_interval: u32,
+    /// Preview pieces taken into account when picking a placement.
+    pub lookahead: usize,
+    /// Chance of picking a random placement instead of the best one.
+    pub mistake_chance: f64,
+    pub soft_drop: bool,
+}
+
+impl BotDifficulty {
+    pub const EASY: Self = Self {
+        action_interval: 20,
+        lookahead: 0,
+        mistake_chance: 0.2,
+        soft_drop: false,
+    };
+    pub const MEDIUM: Self = Self {
+        action_interval: 8,
+        lookahead: 0,
+        mistake_chance: 0.05,
+        soft_drop: true,
+    };
+    pub const HARD: Self = Self {
+        action_interval: 2,
+        lookahead: 1,
+        mistake_chance: 0.,
+        soft_drop: true,
+    };
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Self::EASY),
+            "medium" => Some(Self::MEDIUM),
+            "hard" => Some(Self::HARD),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Bot {
+    difficulty: BotDifficulty,
+    rng: StdRng,
+    /// Falling piece the current target was picked for.
+    piece: Option<Entity>,
+    driver: PieceDriver,
+}
+
+impl Bot {
+    fn plan(
+        &mut self,
+        board: &Board,
+        block: Block,
+        queue: &CurrentBlockWithPreview<PREVIEW_COUNT>,
+    ) {
+        let choice = if self.rng.gen_bool(self.difficulty.mistake_chance) {
+            placements(board, block, true)
+                .choose(&mut self.rng)
+                .map(|x| (false, x.clone()))
+        } else {
+            choose(
+                board,
+                block,
+                queue.preview(),
+                queue.hold(),
+                queue.can_hold(),
+                self.difficulty.lookahead,
+            )
+        };
+        self.driver.hold = choice.as_ref().is_some_and(|(hold, _)| *hold);
+        self.driver.target = choice.map(|(_, x)| x.cells).unwrap_or_default();
+    }
+}
+
+/// Presses buttons to bring the falling piece onto a target placement,
+/// one [`TickInput`] at a time.
+#[derive(Debug, Default)]
+pub struct PieceDriver {
+    /// Cells the falling piece should end up in.
+    pub target: Vec<(usize, usize)>,
+    /// Hold first, `target` is for the piece coming in.
+    pub hold: bool,
+    cooldown: u32,
+}
+
+impl PieceDriver {
+    /// Holds off for `ticks` ticks, e.g. to react to a new piece.
+    pub fn wait(&mut self, ticks: u32) {
+        self.cooldown = ticks;
+    }
+
+    /// Input for this tick, or `None` if the target can't be reached anymore.
+    ///
+    /// Waits `interval` ticks after each button press. Once only dropping is
+    /// left, soft drops if `soft_drop` is set.
+    pub fn next_input(
+        &mut self,
+        board: &Board,
+        block: Block,
+        input: &TickInput,
+        interval: u32,
+        soft_drop: bool,
+    ) -> Option<PlayerInput> {
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return Some(PlayerInput::default());
+        }
+        let button = if self.hold {
+            PlayerInput::HOLD
+        } else {
+            match path_to(board, block, &self.target)?.first() {
+                Some(Move::Left) => PlayerInput::LEFT,
+                Some(Move::Right) => PlayerInput::RIGHT,
+                Some(Move::RotateCw) => PlayerInput::ROTATE_CW,
+                Some(Move::RotateCcw) => PlayerInput::ROTATE_CCW,
+                Some(Move::Drop) | None if soft_drop => return Some(PlayerInput::SOFT_DROP),
+                Some(Move::Drop) | None => return Some(PlayerInput::default()),
+            }
+        };
+        if input.pressed(button) {
+            // let go first, so the next press registers
+            return Some(PlayerInput::default());
+        }
+        self.hold = false;
+        self.cooldown = interval;
+        Some(button)
+    }
+}
+
+fn drive_bot(
+    mut bot: ResMut<Bot>,
+    mut input: ResMut<TickInput>,
+    board: Res<Board>,
+    pieces: Query<(Entity, &Block, &BlockState)>,
+    preview: Res<CurrentBlockWithPreview<PREVIEW_COUNT>>,
+) {
+    let Some((entity, &block, _)) = pieces
+        .iter()
+        .find(|(_, _, state)| **state == BlockState::Falling)
+    else {
+        input.advance(PlayerInput::default());
+        return;
+    };
+    if bot.piece != Some(entity) {
+        bot.piece = Some(entity);
+        bot.plan(&board, block, &preview);
+        let interval = bot.difficulty.action_interval;
+        bot.driver.wait(interval);
+    }
+
+    let BotDifficulty {
+        action_interval,
+        soft_drop,
+        ..
+    } = bot.difficulty;
+    let next = bot
+        .driver
+        .next_input(&board, block, &input, action_interval, soft_drop)
+        .unwrap_or_else(|| {
+            // gravity got in the way, pick something else from here
+            bot.plan(&board, block, &preview);
+            bot.driver
+                .next_input(&board, block, &input, action_interval, soft_drop)
+                .unwrap_or_default()
+        });
+    input.advance(next);
+}
@@ -0,0 +1,337 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::blocks::{
+    blocks::{Block, Board},
+    gravity::drop_block,
+    movement::{move_left, move_right, rotate_block},
+};
+
+// weights from https://codemyroad.wordpress.com/2013/04/14/tetris-ai-the-near-perfect-player/
+const HEIGHT_WEIGHT: f64 = -0.510_066;
+const LINES_WEIGHT: f64 = 0.760_666;
+const HOLES_WEIGHT: f64 = -0.356_63;
+const BUMPINESS_WEIGHT: f64 = -0.184_483;
+
+/// A single step the falling piece can take, one gravity row for `Drop`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    Left,
+    Right,
+    RotateCw,
+    RotateCcw,
+    Drop,
+}
+
+impl Move {
+    const ALL: [Self; 5] = [
+        Self::Left,
+        Self::Right,
+        Self::RotateCw,
+        Self::RotateCcw,
+        Self::Drop,
+    ];
+
+    /// Applies the move with the same rules the game uses.
+    /// Returns whether the piece locked.
+    fn apply(self, board: &mut Board, block: Block) -> bool {
+        match self {
+            Self::Left => move_left(&mut board.inner, block),
+            Self::Right => move_right(&mut board.inner, block),
+            // same as `block_movement_controls`, O never rotates
+            Self::RotateCw | Self::RotateCcw if block == Block::O => {}
+            Self::RotateCw => rotate_block(&mut board.inner, &block, true),
+            Self::RotateCcw => rotate_block(&mut board.inner, &block, false),
+            Self::Drop => return drop_block(&mut board.inner, block, board.pieces + 1),
+        }
+        false
+    }
+}
+
+/// Somewhere the falling piece can lock, and the board right after.
+#[derive(Debug, Clone)]
+pub struct Placement {
+    /// Cells of the piece right before it locks.
+    pub cells: Vec<(usize, usize)>,
+    /// Board after locking and clearing lines.
+    pub board: Board,
+    pub lines: usize,
+}
+
+pub fn falling_cells(board: &Board) -> Vec<(usize, usize)> {
+    board
+        .inner
+        .iter()
+        .enumerate()
+        .flat_map(|(row, x)| {
+            x.iter()
+                .enumerate()
+                .filter(|(_, x)| x.is_falling())
+                .map(move |(col, _)| (row, col))
+        })
+        .collect()
+}
+
+/// Breadth first search over every position the falling piece can reach,
+/// calling `visit` with each one and the moves leading to it until it
+/// returns `true`.
+///
+/// Without `tucks` the piece only moves sideways or rotates before it starts
+/// dropping, which is a lot cheaper and good enough for preview pieces.
+fn search(
+    board: &Board,
+    block: Block,
+    tucks: bool,
+    mut visit: impl FnMut(&Board, &[Move], Option<(&Board, Vec<(usize, usize)>)>) -> bool,
+) {
+    let start = falling_cells(board);
+    let size = start.len();
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([(board.clone(), Vec::new())]);
+    while let Some((state, path)) = queue.pop_front() {
+        if visit(&state, &path, None) {
+            return;
+        }
+        for step in Move::ALL {
+            if !tucks && step != Move::Drop && path.last() == Some(&Move::Drop) {
+                continue;
+            }
+            let mut next = state.clone();
+            let locked = step.apply(&mut next, block);
+            let mut next_path = path.clone();
+            next_path.push(step);
+            if locked {
+                if visit(&state, &next_path, Some((&next, falling_cells(&state)))) {
+                    return;
+                }
+            } else {
+                // rotating into something can cut cells off the piece,
+                // never worth doing on purpose
+                let cells = falling_cells(&next);
+                if cells.len() == size && seen.insert(cells) {
+                    queue.push_back((next, next_path));
+                }
+            }
+        }
+    }
+}
+
+/// Every distinct position the falling piece can lock in.
+pub fn placements(board: &Board, block: Block, tucks: bool) -> Vec<Placement> {
+    let mut locked = HashSet::new();
+    let mut placements = Vec::new();
+    search(board, block, tucks, |_, _, lock| {
+        if let Some((after, cells)) = lock {
+            if locked.insert(cells.clone()) {
+                let mut board = after.clone();
+                let lines = board.clear_lines();
+                placements.push(Placement {
+                    cells,
+                    board,
+                    lines,
+                });
+            }
+        }
+        false
+    });
+    placements
+}
+
+/// Shortest sequence of moves bringing the falling piece onto `target`,
+/// or `None` if it can't get there anymore.
+pub fn path_to(board: &Board, block: Block, target: &[(usize, usize)]) -> Option<Vec<Move>> {
+    let mut found = None;
+    search(board, block, true, |state, path, lock| {
+        if lock.is_none() && falling_cells(state) == target {
+            found = Some(path.to_vec());
+        }
+        found.is_some()
+    });
+    found
+}
+
+/// Scores a board, higher is better.
+pub fn evaluate(board: &Board, lines: usize) -> f64 {
+    let rows = board.inner.len();
+    let cols = board.inner[0].len();
+    let heights = (0..cols)
+        .map(|col| {
+            board
+                .inner
+                .iter()
+                .position(|row| row[col].is_placed())
+                .map_or(0, |top| rows - top)
+        })
+        .collect::<Vec<_>>();
+    let holes = (0..cols)
+        .map(|col| {
+            board
+                .inner
+                .iter()
+                .skip_while(|row| !row[col].is_placed())
+                .filter(|row| !row[col].is_placed())
+                .count()
+        })
+        .sum::<usize>();
+    let bumpiness = heights
+        .windows(2)
+        .map(|x| x[0].abs_diff(x[1]))
+        .sum::<usize>();
+
+    HEIGHT_WEIGHT * heights.iter().sum::<usize>() as f64
+        + LINES_WEIGHT * lines as f64
+        + HOLES_WEIGHT * holes as f64
+        + BUMPINESS_WEIGHT * bumpiness as f64
+}
+
+/// Scores a placement, looking at the best follow-up for each of `upcoming`.
+pub fn score(placement: &Placement, upcoming: &[Block], lines: usize) -> f64 {
+    let lines = lines + placement.lines;
+    let Some((&next, rest)) = upcoming.split_first() else {
+        return evaluate(&placement.board, lines);
+    };
+    let mut board = placement.board.clone();
+    if !board.spawn_block(next) {
+        return f64::NEG_INFINITY;
+    }
+    placements(&board, next, false)
+        .iter()
+        .map(|x| score(x, rest, lines))
+        .fold(f64::NEG_INFINITY, f64::max)
+}
+
+/// Best placement of `block` with `upcoming` next, and its score.
+fn best(board: &Board, block: Block, upcoming: &[Block]) -> Option<(f64, Placement)> {
+    placements(board, block, true)
+        .into_iter()
+        .map(|x| (score(&x, upcoming, 0), x))
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+}
+
+/// Picks where the falling `block` goes, looking `lookahead` pieces of
+/// `preview` ahead. With `can_hold`, what holding brings in instead (`hold`,
+/// or else the next piece) is weighed too.
+///
+/// Returns whether to hold first, and the placement of the piece played.
+pub fn choose(
+    board: &Board,
+    block: Block,
+    preview: &[Block],
+    hold: Option<Block>,
+    can_hold: bool,
+    lookahead: usize,
+) -> Option<(bool, Placement)> {
+    let ahead = |queue: &[Block]| queue[..lookahead.min(queue.len())].to_vec();
+    let played = best(board, block, &ahead(preview));
+    let (swap, rest) = match hold {
+        Some(held) => (held, preview),
+        // the queue moves up
+        None => match preview.split_first() {
+            Some((&next, rest)) => (next, rest),
+            None => return played.map(|(_, x)| (false, x)),
+        },
+    };
+    let mut swapped = board.clone();
+    swapped.clear_falling();
+    let held = (can_hold && swap != block && swapped.spawn_block(swap))
+        .then(|| best(&swapped, swap, &ahead(rest)))
+        .flatten();
+    match (played, held) {
+        (Some(played), Some(held)) if held.0 <= played.0 => Some((false, played.1)),
+        (_, Some(held)) => Some((true, held.1)),
+        (played, None) => played.map(|(_, x)| (false, x)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::blocks::BoardBlockState;
+
+    /// An empty board with `bottom` as its lowest rows, `#` for garbage,
+    /// and `block` spawned on top.
+    fn board(bottom: &[&str], block: Block) -> Board {
+        let mut board = Board::default();
+        let rows = board.inner.len();
+        for (row, line) in board.inner[rows - bottom.len()..].iter_mut().zip(bottom) {
+            for (cell, c) in row.iter_mut().zip(line.chars()) {
+                if c == '#' {
+                    *cell = BoardBlockState::Garbage;
+                }
+            }
+        }
+        assert!(board.spawn_block(block));
+        board
+    }
+
+    const WELL: [&str; 4] = ["#########."; 4];
+
+    #[test]
+    fn o_lands_once_per_column() {
+        let options = placements(&board(&[], Block::O), Block::O, true);
+        let mut columns = options
+            .iter()
+            .map(|x| {
+                assert!(x.cells.iter().all(|&(row, _)| row >= 18));
+                x.cells[0].1
+            })
+            .collect::<Vec<_>>();
+        columns.sort_unstable();
+        assert_eq!(columns, (0..=8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn path_to_slides_over_then_drops() {
+        let board = board(&[], Block::O);
+        let target = [(18, 0), (18, 1), (19, 0), (19, 1)];
+        let path = path_to(&board, Block::O, &target).unwrap();
+        assert_eq!(path.iter().filter(|&&x| x == Move::Left).count(), 4);
+        assert!(path.iter().all(|&x| x == Move::Left || x == Move::Drop));
+
+        // no way past a full row
+        let board = self::board(&["#########.", ".........."], Block::O);
+        assert_eq!(path_to(&board, Block::O, &target), None);
+    }
+
+    #[test]
+    fn evaluate_prefers_low_boards_without_holes() {
+        let empty = Board::default();
+        assert_eq!(evaluate(&empty, 0), 0.);
+        assert!(evaluate(&empty, 1) > evaluate(&empty, 0));
+
+        let mut flat = Board::default();
+        flat.inner[19][0] = BoardBlockState::Garbage;
+        let mut holed = Board::default();
+        holed.inner[18][0] = BoardBlockState::Garbage;
+        assert!(evaluate(&flat, 0) > evaluate(&holed, 0));
+    }
+
+    #[test]
+    fn i_goes_down_the_well() {
+        let board = board(&WELL, Block::I);
+        let (hold, placement) = choose(&board, Block::I, &[Block::O], None, true, 0).unwrap();
+        assert!(!hold);
+        assert_eq!(placement.lines, 4);
+        assert_eq!(placement.cells, [(16, 9), (17, 9), (18, 9), (19, 9)]);
+    }
+
+    #[test]
+    fn holds_when_what_comes_in_fits_better() {
+        let board = board(&WELL, Block::S);
+        let preview = [Block::I, Block::O];
+
+        let (hold, placement) = choose(&board, Block::S, &preview, None, true, 0).unwrap();
+        assert!(hold);
+        assert_eq!(placement.lines, 4);
+
+        // from the hold the queue stays as is
+        let (hold, placement) =
+            choose(&board, Block::S, &[Block::O], Some(Block::I), true, 0).unwrap();
+        assert!(hold);
+        assert_eq!(placement.lines, 4);
+
+        // hold used up on this piece
+        let (hold, placement) = choose(&board, Block::S, &preview, None, false, 0).unwrap();
+        assert!(!hold);
+        assert!(placement.lines < 4);
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use crate::{
+    blocks::input::{Action, GamepadBindings, KeyBindings},
+    settings::Settings,
+    GameState,
+};
+
+/// Opens with F1: lists the keys and controller buttons bound to every
+/// action and lets them be changed. Bindings are saved along with the rest
+/// of the [`Settings`].
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_systems(
+                Update,
+                open_controls.run_if(not(in_state(GameState::Controls))),
+            )
+            .add_systems(OnEnter(GameState::Controls), spawn_controls_screen)
+            .add_systems(
+                Update,
+                (rebind_keys, update_controls_screen)
+                    .chain()
+                    .run_if(in_state(GameState::Controls)),
+            )
+            .add_systems(OnExit(GameState::Controls), despawn_controls_screen);
+    }
+}
+
+#[derive(Resource, Default)]
+pub(crate) struct Rebinding {
+    /// Index into [`Action::ALL`].
+    selected: usize,
+    /// Waiting for a key or button to add to the selected action.
+    listening: bool,
+    /// State to go back to once done.
+    back: GameState,
+}
+
+impl Rebinding {
+    /// Starts over, going to `back` once done.
+    pub(crate) fn back_to(back: GameState) -> Self {
+        Self { back, ..default() }
+    }
+}
+
+#[derive(Component)]
+struct ControlsScreen;
+
+#[derive(Component)]
+struct ControlsText;
+
+fn open_controls(
+    keyboard: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.just_pressed(KeyCode::F1) {
+        *rebinding = Rebinding::back_to(*state.get());
+        next_state.set(GameState::Controls);
+    }
+}
+
+fn spawn_controls_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.9).into(),
+                ..default()
+            },
+            ControlsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 28.,
+                        ..default()
+                    },
+                ),
+                ControlsText,
+            ));
+        });
+}
+
+fn despawn_controls_screen(mut commands: Commands, screen: Query<Entity, With<ControlsScreen>>) {
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn rebind_keys(
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<KeyBindings>,
+    mut pad: ResMut<GamepadBindings>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let action = Action::ALL[rebinding.selected];
+    if rebinding.listening {
+        if let Some(button) = buttons.get_just_pressed().next() {
+            rebinding.listening = false;
+            let buttons = pad.buttons.entry(action).or_default();
+            if !buttons.contains(&button.button_type) {
+                buttons.push(button.button_type);
+            }
+            settings.gamepad = pad.clone();
+            return;
+        }
+        let Some(&key) = keyboard.get_just_pressed().next() else {
+            return;
+        };
+        rebinding.listening = false;
+        if key != KeyCode::Escape {
+            let keys = bindings.0.entry(action).or_default();
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+            settings.controls = bindings.clone();
+        }
+        return;
+    }
+
+    let count = Action::ALL.len();
+    if keyboard.just_pressed(KeyCode::Up) {
+        rebinding.selected = (rebinding.selected + count - 1) % count;
+    }
+    if keyboard.just_pressed(KeyCode::Down) {
+        rebinding.selected = (rebinding.selected + 1) % count;
+    }
+    if keyboard.just_pressed(KeyCode::Return) {
+        rebinding.listening = true;
+    }
+    if keyboard.just_pressed(KeyCode::Back) {
+        bindings.0.insert(action, Vec::new());
+        pad.buttons.insert(action, Vec::new());
+        settings.controls = bindings.clone();
+        settings.gamepad = pad.clone();
+    }
+    if keyboard.just_pressed(KeyCode::Delete) {
+        *bindings = KeyBindings::default();
+        pad.buttons = GamepadBindings::default().buttons;
+        settings.controls = bindings.clone();
+        settings.gamepad = pad.clone();
+    }
+    if keyboard.any_just_pressed([KeyCode::Escape, KeyCode::F1]) {
+        next_state.set(rebinding.back);
+    }
+}
+
+fn update_controls_screen(
+    rebinding: Res<Rebinding>,
+    bindings: Res<KeyBindings>,
+    pad: Res<GamepadBindings>,
+    mut text: Query<&mut Text, With<ControlsText>>,
+) {
+    if !rebinding.is_changed() && !bindings.is_changed() && !pad.is_changed() {
+        return;
+    }
+    let mut value = String::from(
+        "Controls\n\nUp/Down: select  Enter: add key  Backspace: clear  Delete: defaults  Esc: back\n\n",
+    );
+    for (index, action) in Action::ALL.into_iter().enumerate() {
+        let selected = index == rebinding.selected;
+        let keys = if selected && rebinding.listening {
+            String::from("press a key or button, Esc to cancel")
+        } else {
+            bindings
+                .keys(action)
+                .iter()
+                .map(|x| format!("{x:?}"))
+                .chain(pad.buttons(action).iter().map(|x| format!("Pad {x:?}")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let cursor = if selected { ">" } else { " " };
+        value += &format!("{cursor} {:<24} {keys}\n", action.name());
+    }
+    for mut text in &mut text {
+        text.sections[0].value = value.clone();
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    blocks::{
+        blocks::{Level, Lines, Score},
+        input::{Action, ActionInput, InputSource},
+        mode::{format_time, GameClock, GameEnd, GameMode},
+        rng::{seed_text, GameRng},
+        stats::Stats,
+    },
+    storage, GameState,
+};
+
+/// Entries kept per mode.
+const TABLE_SIZE: usize = 10;
+const NAME_LENGTH: usize = 12;
+
+/// A finished game worth remembering.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub name: String,
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub score: usize,
+    pub lines: usize,
+    pub level: u8,
+    /// Frames played.
+    pub frames: u32,
+    pub pps: f64,
+    #[serde(with = "seed_text")]
+    pub seed: u64,
+}
+
+impl Record {
+    /// Whether `self` ranks above `other` in `mode`: Sprint goes by time,
+    /// everything else by score.
+    fn beats(&self, other: &Self, mode: GameMode) -> bool {
+        match mode {
+            GameMode::Sprint => self.frames < other.frames,
+            _ => self.score > other.score,
+        }
+    }
+
+    /// What the entry is ranked by, for display.
+    fn result(&self, mode: GameMode) -> String {
+        match mode {
+            GameMode::Sprint => format_time(self.frames),
+            _ => self.score.to_string(),
+        }
+    }
+}
+
+/// Best games of each solo mode, kept in `highscores.toml` next to the
+/// settings.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    /// Entered on the last record, offered again on the next one.
+    pub last_name: String,
+    pub tables: BTreeMap<GameMode, Vec<Record>>,
+}
+
+impl HighScores {
+    const FILE: &'static str = "highscores.toml";
+
+    pub fn load() -> Self {
+        let Some(text) = storage::read(Self::FILE) else {
+            return Self::default();
+        };
+        toml::from_str(&text).unwrap_or_else(|err| {
+            warn!("ignoring unreadable high scores: {err}");
+            storage::set_aside(Self::FILE);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) {
+        match toml::to_string_pretty(self) {
+            Ok(text) => storage::write(Self::FILE, &text),
+            Err(err) => error!("could not encode high scores: {err}"),
+        }
+    }
+
+    pub fn table(&self, mode: GameMode) -> &[Record] {
+        self.tables.get(&mode).map_or(&[], Vec::as_slice)
+    }
+
+    /// Position `record` would take in the table of `mode`, if it makes it
+    /// in at all.
+    pub fn rank(&self, mode: GameMode, record: &Record) -> Option<usize> {
+        let table = self.table(mode);
+        let rank = table
+            .iter()
+            .position(|x| record.beats(x, mode))
+            .unwrap_or(table.len());
+        (rank < TABLE_SIZE).then_some(rank)
+    }
+
+    pub fn insert(&mut self, mode: GameMode, record: Record) {
+        let Some(rank) = self.rank(mode, &record) else {
+            return;
+        };
+        let table = self.tables.entry(mode).or_default();
+        table.insert(rank, record);
+        table.truncate(TABLE_SIZE);
+    }
+}
+
+/// Keeps [`HighScores`], asks for a name on the results screen when a game
+/// makes it into them and shows them from the start menu.
+///
+/// Only games played by hand count, not bots or replays.
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .init_resource::<PendingRecord>()
+            .init_resource::<ShownTable>()
+            .add_systems(OnEnter(GameState::GameOver), (check_record, forget_typed))
+            .add_systems(Update, enter_name.run_if(in_state(GameState::GameOver)))
+            .add_systems(OnEnter(GameState::HighScores), spawn_screen)
+            .add_systems(
+                Update,
+                (browse_high_scores, update_high_scores)
+                    .chain()
+                    .run_if(in_state(GameState::HighScores)),
+            )
+            .add_systems(OnExit(GameState::HighScores), despawn_screen);
+    }
+}
+
+/// A game that made it into the tables, waiting for a name.
+#[derive(Resource, Default)]
+pub(crate) struct PendingRecord(Option<(GameMode, Record)>);
+
+impl PendingRecord {
+    pub(crate) const fn is_pending(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// The name prompt for the results screen.
+    pub(crate) fn prompt(&self, scores: &HighScores) -> Option<String> {
+        let (mode, record) = self.0.as_ref()?;
+        let rank = scores.rank(*mode, record).unwrap_or_default() + 1;
+        Some(format!(
+            "New {} record: #{rank}\nName: {}_\nEnter to save",
+            mode.name(),
+            record.name,
+        ))
+    }
+}
+
+/// Index into [`GameMode::SOLO`] of the table on screen.
+#[derive(Resource, Default)]
+struct ShownTable(usize);
+
+#[derive(Component)]
+struct HighScoreScreen;
+
+#[derive(Component)]
+struct HighScoreText;
+
+#[allow(clippy::too_many_arguments)]
+fn check_record(
+    mode: Res<GameMode>,
+    end: Res<GameEnd>,
+    source: Res<InputSource>,
+    score: Res<Score>,
+    lines: Res<Lines>,
+    level: Res<Level>,
+    clock: Res<GameClock>,
+    stats: Res<Stats>,
+    rng: Res<GameRng>,
+    scores: Res<HighScores>,
+    mut pending: ResMut<PendingRecord>,
+) {
+    if end.abandoned() {
+        return;
+    }
+    let counts = match *mode {
+        GameMode::Sprint => *end == GameEnd::Finished,
+        GameMode::Marathon | GameMode::Ultra => score.value() > 0,
+        GameMode::Versus => false,
+    };
+    if !counts || *source != InputSource::Keyboard {
+        return;
+    }
+    let record = Record {
+        name: scores.last_name.clone(),
+        date: today(),
+        score: score.value(),
+        lines: lines.total(),
+        level: level.0,
+        frames: clock.frames,
+        pps: stats.pps(&clock),
+        seed: rng.seed(),
+    };
+    if scores.rank(*mode, &record).is_some() {
+        pending.0 = Some((*mode, record));
+    }
+}
+
+fn spawn_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.9).into(),
+                ..default()
+            },
+            HighScoreScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.,
+                        ..default()
+                    },
+                ),
+                HighScoreText,
+            ));
+        });
+}
+
+fn despawn_screen(mut commands: Commands, screen: Query<Entity, With<HighScoreScreen>>) {
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Keys typed while playing are not part of the name.
+fn forget_typed(mut characters: ResMut<Events<ReceivedCharacter>>) {
+    characters.clear();
+}
+
+pub(crate) fn enter_name(
+    mut characters: EventReader<ReceivedCharacter>,
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    mut pending: ResMut<PendingRecord>,
+    mut scores: ResMut<HighScores>,
+) {
+    let Some((mode, record)) = &mut pending.0 else {
+        return;
+    };
+    for character in characters.read().map(|x| x.char) {
+        if (character.is_alphanumeric() || character == ' ')
+            && record.name.chars().count() < NAME_LENGTH
+        {
+            record.name.push(character);
+        }
+    }
+    if keyboard.just_pressed(KeyCode::Back) {
+        record.name.pop();
+    }
+
+    // no keyboard on a phone or couch, so a controller button or a touch
+    // takes the name as it is
+    let confirm = keyboard.just_pressed(KeyCode::Return)
+        || buttons.get_just_pressed().any(|x| {
+            matches!(
+                x.button_type,
+                GamepadButtonType::South | GamepadButtonType::Start
+            )
+        })
+        || touches.any_just_pressed();
+    if !confirm {
+        return;
+    }
+    let name = record.name.trim();
+    record.name = if name.is_empty() {
+        String::from("Player")
+    } else {
+        name.to_owned()
+    };
+    scores.last_name = record.name.clone();
+    scores.insert(*mode, record.clone());
+    scores.save();
+    pending.0 = None;
+}
+
+fn browse_high_scores(
+    actions: ActionInput,
+    keyboard: Res<Input<KeyCode>>,
+    mut shown: ResMut<ShownTable>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let count = GameMode::SOLO.len();
+    if actions.just_pressed(Action::MoveLeft) {
+        shown.0 = (shown.0 + count - 1) % count;
+    }
+    if actions.just_pressed(Action::MoveRight) {
+        shown.0 = (shown.0 + 1) % count;
+    }
+    if actions.just_pressed(Action::Pause) || keyboard.just_pressed(KeyCode::Back) {
+        next_state.set(GameState::StartMenu);
+    }
+}
+
+fn update_high_scores(
+    shown: Res<ShownTable>,
+    scores: Res<HighScores>,
+    mut text: Query<&mut Text, With<HighScoreText>>,
+) {
+    let mode = GameMode::SOLO[shown.0];
+    let mut value = format!("< {} >\n\n", mode.name());
+    let table = scores.table(mode);
+    if table.is_empty() {
+        value += "No records yet\n";
+    }
+    for (rank, record) in table.iter().enumerate() {
+        value += &format!(
+            "{:>2}. {:<12} {:>9}  {:>3} lines  lv {:>2}  {:.2} pps  {}  seed {}\n",
+            rank + 1,
+            record.name,
+            record.result(mode),
+            record.lines,
+            record.level,
+            record.pps,
+            record.date,
+            record.seed,
+        );
+    }
+    for mut text in &mut text {
+        text.sections[0].value = value.clone();
+    }
+}
+
+/// Today's date as `YYYY-MM-DD`, in UTC.
+#[cfg(not(target_arch = "wasm32"))]
+fn today() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs());
+    // days since 1970-01-01 to a civil date, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Today's date as `YYYY-MM-DD`, in local time.
+#[cfg(target_arch = "wasm32")]
+fn today() -> String {
+    let date = js_sys::Date::new_0();
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(seed: u64) -> Record {
+        Record {
+            name: String::from("AAA"),
+            date: String::from("2024-01-01"),
+            score: 1000,
+            lines: 10,
+            level: 9,
+            frames: 3600,
+            pps: 1.5,
+            seed,
+        }
+    }
+
+    #[test]
+    fn any_seed_round_trips() {
+        let scores = HighScores {
+            last_name: String::from("AAA"),
+            tables: BTreeMap::from([(GameMode::Marathon, vec![record(u64::MAX), record(0)])]),
+        };
+        let text = toml::to_string_pretty(&scores).unwrap();
+        let loaded: HighScores = toml::from_str(&text).unwrap();
+        assert_eq!(loaded.tables, scores.tables);
+    }
+
+    #[test]
+    fn numeric_seeds_still_load() {
+        let text = toml::to_string_pretty(&record(1234))
+            .unwrap()
+            .replace("seed = \"1234\"", "seed = 1234");
+        assert!(text.contains("seed = 1234"));
+        let loaded: Record = toml::from_str(&text).unwrap();
+        assert_eq!(loaded.seed, 1234);
+    }
+}
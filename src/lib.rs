@@ -0,0 +1,3 @@
+//! What the game shares with the helper binaries in `src/bin`.
+
+pub mod tbp_messages;
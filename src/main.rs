@@ -1,35 +1,162 @@
 mod blocks;
 mod border;
+mod bot;
+mod controls;
+mod highscores;
+mod menu;
+#[cfg(not(target_arch = "wasm32"))]
+mod net;
+mod options;
+mod pause;
+#[cfg(not(target_arch = "wasm32"))]
+mod replay;
+mod results;
 mod schedule;
+mod settings;
+mod storage;
+#[cfg(not(target_arch = "wasm32"))]
+mod tbp;
+mod touch;
 use bevy::prelude::*;
-use blocks::blocks::TetrisBlockPlugin;
+use blocks::{blocks::TetrisBlockPlugin, handling::Handling, mode::GameMode, rng::use_seed};
+use bot::{BotDifficulty, BotPlugin};
+use controls::ControlsPlugin;
+use highscores::HighScorePlugin;
+use menu::MenuPlugin;
+use options::OptionsPlugin;
+use pause::PausePlugin;
+use results::ResultsPlugin;
+use schedule::SchedulePlugin;
+use settings::SettingsPlugin;
+use touch::TouchPlugin;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, PartialOrd, Ord, Hash, States)]
 pub enum GameState {
-    StartMenu,
     #[default]
+    StartMenu,
+    /// Waiting for the lobby to pair us with a versus opponent.
+    Lobby,
     InGame,
+    Paused,
     GameOver,
-}
-
-fn start_game(
-    mut next_state: ResMut<NextState<GameState>>,
-    state: Res<State<GameState>>,
-    keyboard_input: Res<Input<KeyCode>>,
-) {
-    if keyboard_input.just_pressed(KeyCode::Return) && state.get() == &GameState::StartMenu {
-        next_state.set(GameState::InGame);
-    }
+    Controls,
+    HighScores,
+    Options,
 }
 
 fn main() {
-    App::new()
-        .add_state::<GameState>()
+    let mut app = App::new();
+    app.add_state::<GameState>()
         .add_plugins(DefaultPlugins)
         .add_systems(Startup, setup)
-        .add_systems(Update, start_game)
+        .add_plugins(SchedulePlugin)
+        .add_plugins(SettingsPlugin)
         .add_plugins(TetrisBlockPlugin)
-        .run();
+        .add_plugins(ControlsPlugin)
+        .add_plugins(HighScorePlugin)
+        .add_plugins(MenuPlugin)
+        .add_plugins(OptionsPlugin)
+        .add_plugins(PausePlugin)
+        .add_plugins(ResultsPlugin)
+        .add_plugins(TouchPlugin);
+
+    // only for this run, the saved settings keep their own
+    let mut handling = *app.world.resource::<Handling>();
+    for (flag, value) in [
+        ("--das", &mut handling.das),
+        ("--arr", &mut handling.arr),
+        ("--sdf", &mut handling.soft_drop_factor),
+        ("--dcd", &mut handling.das_cut_delay),
+        ("--line-clear-delay", &mut handling.line_clear_delay),
+    ] {
+        if let Some(arg) = cli_arg(flag) {
+            match arg.parse() {
+                Ok(frames) => *value = frames,
+                Err(err) => eprintln!("invalid {flag} {arg}: {err}"),
+            }
+        }
+    }
+    if let Some(arg) = cli_arg("--are") {
+        match arg.parse() {
+            _ if arg == "nes" => handling.nes_are = true,
+            Ok(frames) => handling.are = frames,
+            Err(err) => eprintln!("invalid --are {arg}: {err}"),
+        }
+    }
+    app.insert_resource(handling);
+
+    if let Some(rate) = cli_arg("--tick-rate") {
+        match rate.parse::<f64>() {
+            Ok(rate) if rate > 0. => {
+                app.insert_resource(Time::<Fixed>::from_hz(rate));
+            }
+            _ => eprintln!("invalid tick rate {rate}, expected ticks per second"),
+        }
+    }
+
+    if let Some(seed) = cli_arg("--seed") {
+        match seed.parse() {
+            Ok(seed) => use_seed(&mut app.world, seed),
+            Err(err) => eprintln!("invalid seed {seed}: {err}"),
+        }
+    }
+
+    if let Some(name) = cli_arg("--mode") {
+        match GameMode::from_name(&name) {
+            Some(mode) => {
+                app.insert_resource(mode);
+            }
+            None => eprintln!("unknown mode {name}, expected marathon, sprint or ultra"),
+        }
+    }
+
+    if let Some(name) = cli_arg("--bot") {
+        match BotDifficulty::from_name(&name) {
+            Some(difficulty) => {
+                app.add_plugins(BotPlugin { difficulty });
+            }
+            None => eprintln!("unknown bot difficulty {name}, expected easy, medium or hard"),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(command) = cli_arg("--tbp") {
+        app.add_plugins(tbp::TbpPlugin { command });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(addr) = cli_arg("--connect") {
+        app.add_plugins(net::NetPlugin { addr });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(dir) = cli_arg("--record") {
+        app.add_plugins(replay::RecordPlugin { dir: dir.into() });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = cli_arg("--replay") {
+        app.add_plugins(replay::PlaybackPlugin { path: path.into() });
+    }
+
+    // nobody to pick anything on the start menu
+    if ["--bot", "--tbp", "--replay"]
+        .into_iter()
+        .any(|x| cli_arg(x).is_some())
+    {
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::InGame);
+    }
+
+    app.run();
+}
+
+/// Value following `flag` on the command line, e.g. `--connect 127.0.0.1:7878`.
+fn cli_arg(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|x| x != flag);
+    args.next()?;
+    args.next()
 }
 
 fn setup(mut commands: Commands) {
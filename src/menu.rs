@@ -0,0 +1,279 @@
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    blocks::{
+        blocks::{Level, StartLevel},
+        mode::GameMode,
+    },
+    controls::Rebinding,
+    settings::Settings,
+    GameState,
+};
+
+/// Highest level a game can start at, from there on gravity doesn't get
+/// any faster.
+const MAX_START_LEVEL: u8 = 29;
+
+/// Menu navigation from the keyboard or any controller.
+///
+/// Unlike gameplay this doesn't go through the bindings, so the menus can
+/// always be found again with the arrows and Enter.
+#[derive(SystemParam)]
+pub struct MenuInput<'w> {
+    keyboard: Res<'w, Input<KeyCode>>,
+    buttons: Res<'w, Input<GamepadButton>>,
+}
+
+impl MenuInput<'_> {
+    fn just_pressed(&self, keys: &[KeyCode], buttons: &[GamepadButtonType]) -> bool {
+        self.keyboard.any_just_pressed(keys.iter().copied())
+            || self
+                .buttons
+                .get_just_pressed()
+                .any(|x| buttons.contains(&x.button_type))
+    }
+
+    pub fn up(&self) -> bool {
+        self.just_pressed(&[KeyCode::Up], &[GamepadButtonType::DPadUp])
+    }
+
+    pub fn down(&self) -> bool {
+        self.just_pressed(&[KeyCode::Down], &[GamepadButtonType::DPadDown])
+    }
+
+    pub fn left(&self) -> bool {
+        self.just_pressed(&[KeyCode::Left], &[GamepadButtonType::DPadLeft])
+    }
+
+    pub fn right(&self) -> bool {
+        self.just_pressed(&[KeyCode::Right], &[GamepadButtonType::DPadRight])
+    }
+
+    pub fn confirm(&self) -> bool {
+        self.just_pressed(
+            &[KeyCode::Return],
+            &[GamepadButtonType::South, GamepadButtonType::Start],
+        )
+    }
+}
+
+/// Title screen, shown in [`GameState::StartMenu`]: pick a mode and level
+/// (remembered in the [`Settings`]) and start, or go to the other screens,
+/// the settings among them.
+///
+/// Works with the keyboard, controllers ([`MenuInput`]) and by clicking or
+/// tapping the entries.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Menu>()
+            .add_systems(OnEnter(GameState::StartMenu), spawn_menu)
+            .add_systems(
+                Update,
+                (navigate_menu, update_menu)
+                    .chain()
+                    .run_if(in_state(GameState::StartMenu)),
+            )
+            .add_systems(OnExit(GameState::StartMenu), despawn_menu);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuItem {
+    Mode,
+    Level,
+    Start,
+    Settings,
+    Controls,
+    HighScores,
+    Quit,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const ITEMS: [MenuItem; 7] = [
+    MenuItem::Mode,
+    MenuItem::Level,
+    MenuItem::Start,
+    MenuItem::Settings,
+    MenuItem::Controls,
+    MenuItem::HighScores,
+    MenuItem::Quit,
+];
+/// A browser tab can't be quit.
+#[cfg(target_arch = "wasm32")]
+const ITEMS: [MenuItem; 6] = [
+    MenuItem::Mode,
+    MenuItem::Level,
+    MenuItem::Start,
+    MenuItem::Settings,
+    MenuItem::Controls,
+    MenuItem::HighScores,
+];
+
+/// Index into [`ITEMS`] of the selected entry. Kept when leaving the menu,
+/// so coming back from e.g. the high scores lands on the same entry.
+#[derive(Resource, Default)]
+struct Menu {
+    selected: usize,
+}
+
+#[derive(Component)]
+struct MenuScreen;
+
+#[derive(Component)]
+struct MenuEntry(usize);
+
+fn spawn_menu(mut commands: Commands, mut menu: ResMut<Menu>) {
+    // start is what most people are after
+    if menu.selected == 0 {
+        menu.selected = ITEMS.iter().position(|&x| x == MenuItem::Start).unwrap();
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(12.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.9).into(),
+                ..default()
+            },
+            MenuScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "TETRIS",
+                TextStyle {
+                    font_size: 72.,
+                    ..default()
+                },
+            ));
+            for index in 0..ITEMS.len() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::axes(Val::Px(16.), Val::Px(4.)),
+                                ..default()
+                            },
+                            background_color: Color::NONE.into(),
+                            ..default()
+                        },
+                        MenuEntry(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 32.,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn despawn_menu(mut commands: Commands, screen: Query<Entity, With<MenuScreen>>) {
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn navigate_menu(
+    input: MenuInput,
+    clicked: Query<(&Interaction, &MenuEntry), Changed<Interaction>>,
+    mut menu: ResMut<Menu>,
+    mut settings: ResMut<Settings>,
+    game: (ResMut<GameMode>, ResMut<StartLevel>, ResMut<Level>),
+    mut rebinding: ResMut<Rebinding>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let count = ITEMS.len();
+    if input.up() {
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if input.down() {
+        menu.selected = (menu.selected + 1) % count;
+    }
+    let mut step = i32::from(input.right()) - i32::from(input.left());
+    let mut confirm = input.confirm();
+    for (interaction, entry) in &clicked {
+        if *interaction == Interaction::Pressed {
+            menu.selected = entry.0;
+            confirm = true;
+        }
+    }
+    // confirming a value cycles through them, for clicks and taps
+    if confirm && step == 0 {
+        step = 1;
+    }
+
+    let (mut mode, mut start_level, mut level) = game;
+    match ITEMS[menu.selected] {
+        // starts from `--mode` if there's one, but only a pick is remembered
+        MenuItem::Mode if step != 0 => {
+            let modes = GameMode::SOLO;
+            let current = modes.iter().position(|&x| x == *mode).unwrap_or_default();
+            let next = (current as i32 + step).rem_euclid(modes.len() as i32);
+            *mode = modes[next as usize];
+            settings.game.mode = *mode;
+        }
+        MenuItem::Level if step != 0 => {
+            let levels = i32::from(MAX_START_LEVEL) + 1;
+            let next = (i32::from(settings.game.level) + step).rem_euclid(levels);
+            settings.game.level = next as u8;
+        }
+        MenuItem::Start if confirm => {
+            *start_level = StartLevel(settings.game.level);
+            *level = Level(settings.game.level);
+            next_state.set(GameState::InGame);
+        }
+        MenuItem::Settings if confirm => next_state.set(GameState::Options),
+        MenuItem::Controls if confirm => {
+            *rebinding = Rebinding::back_to(GameState::StartMenu);
+            next_state.set(GameState::Controls);
+        }
+        MenuItem::HighScores if confirm => next_state.set(GameState::HighScores),
+        MenuItem::Quit if confirm => exit.send(AppExit),
+        _ => {}
+    }
+}
+
+fn update_menu(
+    menu: Res<Menu>,
+    settings: Res<Settings>,
+    mode: Res<GameMode>,
+    entries: Query<(&MenuEntry, &Children)>,
+    spawned: Query<(), Added<MenuEntry>>,
+    mut texts: Query<&mut Text>,
+) {
+    if !menu.is_changed() && !settings.is_changed() && !mode.is_changed() && spawned.is_empty() {
+        return;
+    }
+    for (entry, children) in &entries {
+        let label = match ITEMS[entry.0] {
+            MenuItem::Mode => format!("Mode: < {} >", mode.name()),
+            MenuItem::Level => format!("Level: < {} >", settings.game.level),
+            MenuItem::Start => String::from("Start"),
+            MenuItem::Settings => String::from("Settings"),
+            MenuItem::Controls => String::from("Controls"),
+            MenuItem::HighScores => String::from("High scores"),
+            MenuItem::Quit => String::from("Quit"),
+        };
+        let selected = entry.0 == menu.selected;
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = label.clone();
+            text.sections[0].style.color = if selected { Color::GOLD } else { Color::WHITE };
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    sync::{
+        mpsc::{channel, Receiver},
+        Mutex,
+    },
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    blocks::{
+        blocks::{Level, LinesIncrementEvent, Links, StartLevel},
+        drawer::DrawBlockEvent,
+        garbage::{attack_for_lines, PendingGarbage},
+        handling::Handling,
+        input::{PlayerInput, TickInput},
+        mode::{GameEnd, GameMode},
+        rng::GameRng,
+        skin::Mino,
+    },
+    schedule::InGameSet,
+    GameState,
+};
+
+use self::rollback::{RemoteTick, RollbackSession};
+
+mod rollback;
+
+/// Column offset of the opponent board, relative to the local one.
+const OPPONENT_COL_OFFSET: isize = -15;
+
+/// Connects to a lobby (see `src/bin/lobby.rs`) and plays a versus match
+/// against whoever it pairs us with.
+///
+/// Only inputs are exchanged; the opponent's board is simulated locally
+/// with rollback, see [`RollbackSession`].
+pub struct NetPlugin {
+    pub addr: String,
+}
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        let stream = match TcpStream::connect(&self.addr) {
+            Ok(stream) => stream,
+            Err(err) => {
+                error!("could not connect to lobby at {}: {err}", self.addr);
+                return;
+            }
+        };
+        let Ok(reader) = stream.try_clone() else {
+            error!("could not clone lobby connection");
+            return;
+        };
+        let (sender, incoming) = channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                match NetMessage::decode(&line) {
+                    Some(message) => {
+                        if sender.send(message).is_err() {
+                            break;
+                        }
+                    }
+                    None => warn!("ignoring malformed message: {line}"),
+                }
+            }
+        });
+
+        // the opponent is simulated from the default level, so we play
+        // from there too
+        app.insert_resource(NetConnection {
+            stream,
+            incoming: Mutex::new(incoming),
+        })
+        .insert_resource(GameMode::Versus)
+        .insert_resource(StartLevel::default())
+        .insert_resource(Level::default())
+        .init_resource::<NetMatch>()
+        .add_systems(Startup, wait_for_match)
+        .add_systems(Update, receive_messages)
+        .add_systems(
+            FixedUpdate,
+            send_input
+                .after(InGameSet::UserInput)
+                .before(InGameSet::BoardInitUpdate)
+                .run_if(in_state(GameState::InGame)),
+        )
+        .add_systems(FixedUpdate, send_attack.in_set(InGameSet::InfoUpdate))
+        .add_systems(
+            FixedUpdate,
+            advance_opponent
+                .after(InGameSet::InfoUpdate)
+                .run_if(in_state(GameState::InGame)),
+        )
+        .add_systems(Update, draw_opponent.in_set(InGameSet::BoardDrawer))
+        .add_systems(OnEnter(GameState::GameOver), send_lost);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetMessage {
+    /// Sent by the lobby: (re)start the match with this seed.
+    Start { seed: u64 },
+    /// The sender's input for one of their ticks, and the garbage that
+    /// entered their queue on it.
+    Input {
+        round: u32,
+        tick: u32,
+        input: PlayerInput,
+        garbage: usize,
+    },
+    /// Garbage lines the receiver has to take.
+    Garbage(usize),
+    /// The sender topped out.
+    Lost,
+    /// The sender's handling, so their game can be simulated faithfully.
+    Handling(Handling),
+}
+
+impl NetMessage {
+    pub fn encode(&self) -> String {
+        match self {
+            Self::Start { seed } => format!("START {seed}"),
+            Self::Input {
+                round,
+                tick,
+                input,
+                garbage,
+            } => format!("INPUT {round} {tick} {} {garbage}", input.0),
+            Self::Garbage(lines) => format!("GARBAGE {lines}"),
+            Self::Lost => String::from("LOST"),
+            Self::Handling(handling) => format!("HANDLING {}", handling.encode()),
+        }
+    }
+
+    pub fn decode(line: &str) -> Option<Self> {
+        let (kind, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match kind {
+            "START" => rest.parse().ok().map(|seed| Self::Start { seed }),
+            "INPUT" => {
+                let mut fields = rest.split(' ').map(str::parse::<u64>);
+                let mut next = || fields.next()?.ok();
+                Some(Self::Input {
+                    round: next()?.try_into().ok()?,
+                    tick: next()?.try_into().ok()?,
+                    input: PlayerInput(next()?.try_into().ok()?),
+                    garbage: next()?.try_into().ok()?,
+                })
+            }
+            "GARBAGE" => rest.parse().ok().map(Self::Garbage),
+            "LOST" => Some(Self::Lost),
+            "HANDLING" => Handling::decode(rest).map(Self::Handling),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct NetConnection {
+    stream: TcpStream,
+    incoming: Mutex<Receiver<NetMessage>>,
+}
+
+impl NetConnection {
+    fn send(&self, message: &NetMessage) {
+        if let Err(err) = writeln!(&self.stream, "{}", message.encode()) {
+            warn!("failed to send to lobby: {err}");
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct NetMatch {
+    /// Number of `START`s received, so stale input from a previous round
+    /// can be told apart.
+    round: u32,
+    /// Next local tick of this round.
+    tick: u32,
+    /// Garbage received from the opponent, queued on our next tick.
+    incoming_garbage: usize,
+    /// Kept across rounds, it's only sent once per `START`.
+    opponent_handling: Handling,
+    session: Option<RollbackSession>,
+}
+
+fn wait_for_match(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Lobby);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn receive_messages(
+    connection: Res<NetConnection>,
+    mut net_match: ResMut<NetMatch>,
+    mut pending: ResMut<PendingGarbage>,
+    mut rng: ResMut<GameRng>,
+    handling: Res<Handling>,
+    state: Res<State<GameState>>,
+    mut end: ResMut<GameEnd>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let incoming = connection.incoming.lock().unwrap();
+    for message in incoming.try_iter() {
+        match message {
+            NetMessage::Start { seed } => {
+                *rng = GameRng::new(seed);
+                info!("match starting with seed {}", rng.seed());
+                pending.0 = 0;
+                *net_match = NetMatch {
+                    round: net_match.round + 1,
+                    opponent_handling: net_match.opponent_handling,
+                    session: Some(RollbackSession::new(seed, net_match.opponent_handling)),
+                    ..default()
+                };
+                connection.send(&NetMessage::Handling(*handling));
+                // leaving the results resets the board, a game in progress
+                // (or the lobby) goes through them without showing them
+                if *state.get() == GameState::GameOver {
+                    next_state.set(GameState::InGame);
+                } else {
+                    *end = GameEnd::Restarted;
+                    next_state.set(GameState::GameOver);
+                }
+            }
+            NetMessage::Input {
+                round,
+                tick,
+                input,
+                garbage,
+            } => {
+                if round != net_match.round {
+                    continue;
+                }
+                if let Some(session) = &mut net_match.session {
+                    session.confirm(tick, RemoteTick { input, garbage });
+                }
+            }
+            NetMessage::Garbage(lines) => net_match.incoming_garbage += lines,
+            NetMessage::Lost => {
+                let rollbacks = net_match.session.as_ref().map_or(0, |x| x.rollbacks());
+                info!("opponent topped out, {rollbacks} rollbacks this round");
+            }
+            NetMessage::Handling(handling) => {
+                net_match.opponent_handling = handling;
+                if let Some(session) = &mut net_match.session {
+                    session.set_handling(handling);
+                }
+            }
+        }
+    }
+}
+
+fn send_input(
+    connection: Res<NetConnection>,
+    input: Res<TickInput>,
+    mut net_match: ResMut<NetMatch>,
+    mut pending: ResMut<PendingGarbage>,
+) {
+    let garbage = std::mem::take(&mut net_match.incoming_garbage);
+    pending.0 += garbage;
+    connection.send(&NetMessage::Input {
+        round: net_match.round,
+        tick: net_match.tick,
+        input: input.current(),
+        garbage,
+    });
+    net_match.tick += 1;
+}
+
+fn advance_opponent(mut net_match: ResMut<NetMatch>) {
+    let tick = net_match.tick;
+    if let Some(session) = &mut net_match.session {
+        session.advance_to(tick);
+    }
+}
+
+fn send_attack(connection: Res<NetConnection>, mut lines: EventReader<LinesIncrementEvent>) {
+    for lines in lines.read() {
+        let attack = attack_for_lines(lines.0);
+        if attack > 0 {
+            connection.send(&NetMessage::Garbage(attack));
+        }
+    }
+}
+
+/// A lobby restart isn't a loss.
+fn send_lost(connection: Res<NetConnection>, end: Res<GameEnd>) {
+    if !end.abandoned() {
+        connection.send(&NetMessage::Lost);
+    }
+}
+
+fn draw_opponent(net_match: Res<NetMatch>, mut event: EventWriter<DrawBlockEvent>) {
+    let Some(session) = &net_match.session else {
+        return;
+    };
+    for (u_row, row) in session.board().inner.iter().enumerate() {
+        for (u_col, block) in row.iter().enumerate() {
+            if let Some(mino) = Mino::of(*block) {
+                event.send(DrawBlockEvent {
+                    row: u_row as isize,
+                    col: u_col as isize + OPPONENT_COL_OFFSET,
+                    mino,
+                    links: Links::at(&session.board().inner, u_row, u_col),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        let handling = Handling {
+            das: 7,
+            arr: 0,
+            are: 10,
+            initial_actions: true,
+            ..default()
+        };
+        for message in [
+            NetMessage::Start { seed: u64::MAX },
+            NetMessage::Input {
+                round: 3,
+                tick: 1200,
+                input: PlayerInput(PlayerInput::LEFT.0 | PlayerInput::HOLD.0),
+                garbage: 2,
+            },
+            NetMessage::Garbage(4),
+            NetMessage::Lost,
+            NetMessage::Handling(handling),
+        ] {
+            assert_eq!(NetMessage::decode(&message.encode()), Some(message));
+        }
+    }
+
+    #[test]
+    fn malformed_messages_are_rejected() {
+        for line in ["", "START", "START x", "INPUT 1 2 3", "INPUT 1 2 256 0", "HELLO"] {
+            assert_eq!(NetMessage::decode(line), None, "{line}");
+        }
+    }
+}
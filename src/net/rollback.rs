@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    blocks::{
+        blocks::{Board, TetrisSimulationPlugin},
+        garbage::PendingGarbage,
+        handling::Handling,
+        input::{PlayerInput, TickInput},
+        rng::GameRng,
+        snapshot::GameSnapshot,
+    },
+    schedule::SchedulePlugin,
+    GameState,
+};
+
+/// How far ahead of the last confirmed remote tick we are willing to guess.
+/// Past this the opponent's board just waits for their input to arrive.
+const MAX_PREDICTION_TICKS: u32 = 12;
+
+/// What the remote player did during one tick.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RemoteTick {
+    pub input: PlayerInput,
+    /// Garbage lines that landed in their queue this tick.
+    pub garbage: usize,
+}
+
+/// GGPO-style simulation of the remote player.
+///
+/// Their game runs locally in its own headless `World`, one tick per local
+/// tick, predicting their input by repeating the last confirmed one. When a
+/// confirmed input disagrees with what was predicted, the world is restored
+/// to the snapshot taken before that tick and resimulated.
+#[derive(Resource)]
+pub struct RollbackSession {
+    world: World,
+    /// Next tick to be simulated.
+    tick: u32,
+    confirmed: Vec<RemoteTick>,
+    /// Input each simulated tick was run with, confirmed or predicted.
+    simulated: Vec<RemoteTick>,
+    /// State right before each tick that may still be rolled back.
+    snapshots: VecDeque<(u32, GameSnapshot)>,
+    rollbacks: usize,
+    /// Tick the remote player topped out on, nothing is simulated past it.
+    topped_out: Option<u32>,
+}
+
+impl RollbackSession {
+    pub fn new(seed: u64, handling: Handling) -> Self {
+        let mut app = App::new();
+        app.insert_resource(GameRng::new(seed))
+            .insert_resource(handling)
+            .insert_resource(Time::<()>::default())
+            .add_state::<GameState>()
+            .insert_resource(State::new(GameState::InGame))
+            .add_plugins(SchedulePlugin)
+            .add_plugins(TetrisSimulationPlugin);
+        Self {
+            world: std::mem::take(&mut app.world),
+            tick: 0,
+            confirmed: Vec::new(),
+            simulated: Vec::new(),
+            snapshots: VecDeque::new(),
+            rollbacks: 0,
+            topped_out: None,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        self.world.resource::<Board>()
+    }
+
+    pub const fn rollbacks(&self) -> usize {
+        self.rollbacks
+    }
+
+    /// Switches to the handling the remote player actually uses,
+    /// resimulating whatever was predicted with the old one.
+    pub fn set_handling(&mut self, handling: Handling) {
+        if *self.world.resource::<Handling>() == handling {
+            return;
+        }
+        self.world.insert_resource(handling);
+        if self.tick > 0 {
+            self.rollback_to(0);
+        }
+    }
+
+    /// Records the remote input for `tick`, rolling back if it was mispredicted.
+    pub fn confirm(&mut self, tick: u32, remote: RemoteTick) {
+        if tick as usize != self.confirmed.len() {
+            warn!(
+                "out of order remote tick {tick}, expected {}",
+                self.confirmed.len()
+            );
+            return;
+        }
+        self.confirmed.push(remote);
+        if self
+            .simulated
+            .get(tick as usize)
+            .is_some_and(|&predicted| predicted != remote)
+        {
+            self.rollback_to(tick);
+        }
+        let confirmed = self.confirmed.len() as u32;
+        while self
+            .snapshots
+            .front()
+            .is_some_and(|(tick, _)| *tick < confirmed)
+        {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Simulates (or resimulates) the remote game up to `target`.
+    pub fn advance_to(&mut self, target: u32) {
+        let limit = self.confirmed.len() as u32 + MAX_PREDICTION_TICKS;
+        while self.topped_out.is_none() && self.tick < target.min(limit) {
+            let remote = self
+                .confirmed
+                .get(self.tick as usize)
+                .copied()
+                .unwrap_or_else(|| self.predict());
+            self.snapshots
+                .push_back((self.tick, GameSnapshot::save(&mut self.world)));
+            self.step(remote);
+            self.simulated.push(remote);
+            self.tick += 1;
+        }
+    }
+
+    fn predict(&self) -> RemoteTick {
+        RemoteTick {
+            input: self.confirmed.last().map_or_else(PlayerInput::default, |x| x.input),
+            garbage: 0,
+        }
+    }
+
+    fn rollback_to(&mut self, tick: u32) {
+        let Some(index) = self.snapshots.iter().position(|(x, _)| *x == tick) else {
+            error!("no snapshot left to roll back to tick {tick}");
+            return;
+        };
+        self.snapshots[index].1.restore(&mut self.world);
+        self.snapshots.truncate(index);
+        self.simulated.truncate(tick as usize);
+        self.tick = tick;
+        self.rollbacks += 1;
+        if self.topped_out.is_some_and(|x| x >= tick) {
+            self.topped_out = None;
+        }
+    }
+
+    fn step(&mut self, remote: RemoteTick) {
+        self.world.resource_mut::<TickInput>().advance(remote.input);
+        self.world.resource_mut::<PendingGarbage>().0 += remote.garbage;
+        let timestep = self.world.resource::<Time<Fixed>>().timestep();
+        self.world.resource_mut::<Time>().advance_by(timestep);
+        self.world.run_schedule(First);
+        self.world.run_schedule(FixedUpdate);
+        // there are no state transitions in here, a top out just ends the game
+        let mut next_state = self.world.resource_mut::<NextState<GameState>>();
+        if next_state.0.take() == Some(GameState::GameOver) {
+            self.topped_out = Some(self.tick);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Confirms `input` for every tick and simulates along, up to `ticks`.
+    fn play(session: &mut RollbackSession, input: PlayerInput, ticks: u32) {
+        for tick in session.confirmed.len() as u32..ticks {
+            session.confirm(tick, RemoteTick { input, garbage: 0 });
+            session.advance_to(tick + 1);
+        }
+    }
+
+    #[test]
+    fn simulates_until_the_opponent_tops_out() {
+        let mut session = RollbackSession::new(1, Handling::default());
+        // untouched pieces stack up in the middle until one can't spawn
+        play(&mut session, PlayerInput::default(), 20_000);
+        let topped_out = session.topped_out.expect("never topped out");
+        assert_eq!(session.tick, topped_out + 1);
+        assert!(session.board().pieces > 0);
+    }
+
+    #[test]
+    fn mispredicted_input_is_resimulated() {
+        let mut session = RollbackSession::new(1, Handling::default());
+        play(&mut session, PlayerInput::default(), 10);
+        session.advance_to(15);
+        let predicted = session.board().clone();
+
+        // the remote player actually moved left on tick 10
+        session.confirm(
+            10,
+            RemoteTick {
+                input: PlayerInput::LEFT,
+                garbage: 0,
+            },
+        );
+        session.advance_to(15);
+        assert_eq!(session.rollbacks(), 1);
+        assert_ne!(session.board().inner, predicted.inner);
+    }
+}
@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+
+use crate::{blocks::drawer::LineClearStyle, menu::MenuInput, settings::Settings, GameState};
+
+/// Settings screen, reached from the start menu: the `[visuals]` and
+/// `[training]` switches of the [`Settings`], saved as they change.
+///
+/// The skin is a file name, so it's only set in `settings.toml`. Handling
+/// and controls have their own flags and screen.
+pub struct OptionsPlugin;
+
+impl Plugin for OptionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Options>()
+            .add_systems(OnEnter(GameState::Options), spawn_options)
+            .add_systems(
+                Update,
+                (navigate_options, update_options)
+                    .chain()
+                    .run_if(in_state(GameState::Options)),
+            )
+            .add_systems(OnExit(GameState::Options), despawn_options);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    StatsPanel,
+    PiecePanel,
+    Connected,
+    LineClear,
+    HideBoardOnPause,
+    GameOverFill,
+    TerminalBoard,
+    FinesseTrainer,
+    FinesseRetry,
+    Back,
+}
+
+const ENTRIES: [Entry; 10] = [
+    Entry::StatsPanel,
+    Entry::PiecePanel,
+    Entry::Connected,
+    Entry::LineClear,
+    Entry::HideBoardOnPause,
+    Entry::GameOverFill,
+    Entry::TerminalBoard,
+    Entry::FinesseTrainer,
+    Entry::FinesseRetry,
+    Entry::Back,
+];
+
+const LINE_CLEAR_STYLES: [LineClearStyle; 3] = [
+    LineClearStyle::Wipe,
+    LineClearStyle::Flash,
+    LineClearStyle::Instant,
+];
+
+impl Entry {
+    fn label(self, settings: &Settings) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        let visuals = &settings.visuals;
+        let training = &settings.training;
+        match self {
+            Self::StatsPanel => format!("Stats panel: {}", on_off(visuals.stats_panel)),
+            Self::PiecePanel => format!("Piece panel: {}", on_off(visuals.piece_panel)),
+            Self::Connected => format!("Connected pieces: {}", on_off(visuals.connected)),
+            Self::LineClear => format!("Line clear: < {:?} >", visuals.line_clear),
+            Self::HideBoardOnPause => {
+                format!(
+                    "Hide board on pause: {}",
+                    on_off(visuals.hide_board_on_pause)
+                )
+            }
+            Self::GameOverFill => format!("Game over fill: {}", on_off(visuals.game_over_fill)),
+            Self::TerminalBoard => format!("Terminal board: {}", on_off(visuals.terminal_board)),
+            Self::FinesseTrainer => {
+                format!("Finesse trainer: {}", on_off(training.finesse_trainer))
+            }
+            Self::FinesseRetry => format!("Finesse retry: {}", on_off(training.finesse_retry)),
+            Self::Back => String::from("Back"),
+        }
+    }
+
+    /// Flips a switch, or steps through the values of the others.
+    fn change(self, settings: &mut Settings, step: i32) {
+        let visuals = &mut settings.visuals;
+        let training = &mut settings.training;
+        let flip = |on: &mut bool| *on = !*on;
+        match self {
+            Self::StatsPanel => flip(&mut visuals.stats_panel),
+            Self::PiecePanel => flip(&mut visuals.piece_panel),
+            Self::Connected => flip(&mut visuals.connected),
+            Self::LineClear => {
+                let current = LINE_CLEAR_STYLES
+                    .iter()
+                    .position(|&x| x == visuals.line_clear)
+                    .unwrap_or_default();
+                let next = (current as i32 + step).rem_euclid(LINE_CLEAR_STYLES.len() as i32);
+                visuals.line_clear = LINE_CLEAR_STYLES[next as usize];
+            }
+            Self::HideBoardOnPause => flip(&mut visuals.hide_board_on_pause),
+            Self::GameOverFill => flip(&mut visuals.game_over_fill),
+            Self::TerminalBoard => flip(&mut visuals.terminal_board),
+            Self::FinesseTrainer => flip(&mut training.finesse_trainer),
+            Self::FinesseRetry => flip(&mut training.finesse_retry),
+            Self::Back => {}
+        }
+    }
+}
+
+/// Index into [`ENTRIES`] of the selected entry.
+#[derive(Resource, Default)]
+struct Options {
+    selected: usize,
+}
+
+#[derive(Component)]
+struct OptionsScreen;
+
+#[derive(Component)]
+struct OptionsEntry(usize);
+
+fn spawn_options(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(6.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.9).into(),
+                ..default()
+            },
+            OptionsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "SETTINGS",
+                TextStyle {
+                    font_size: 48.,
+                    ..default()
+                },
+            ));
+            for index in 0..ENTRIES.len() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::axes(Val::Px(16.), Val::Px(2.)),
+                                ..default()
+                            },
+                            background_color: Color::NONE.into(),
+                            ..default()
+                        },
+                        OptionsEntry(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 24.,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn despawn_options(mut commands: Commands, screen: Query<Entity, With<OptionsScreen>>) {
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn navigate_options(
+    input: MenuInput,
+    keyboard: Res<Input<KeyCode>>,
+    clicked: Query<(&Interaction, &OptionsEntry), Changed<Interaction>>,
+    mut options: ResMut<Options>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let count = ENTRIES.len();
+    if input.up() {
+        options.selected = (options.selected + count - 1) % count;
+    }
+    if input.down() {
+        options.selected = (options.selected + 1) % count;
+    }
+    let mut step = i32::from(input.right()) - i32::from(input.left());
+    let mut confirm = input.confirm();
+    for (interaction, entry) in &clicked {
+        if *interaction == Interaction::Pressed {
+            options.selected = entry.0;
+            confirm = true;
+        }
+    }
+    if confirm && step == 0 {
+        step = 1;
+    }
+
+    let entry = ENTRIES[options.selected];
+    let back = entry == Entry::Back && confirm;
+    // not the pause binding, Enter is on it too
+    if back || keyboard.any_just_pressed([KeyCode::Escape, KeyCode::Back]) {
+        next_state.set(GameState::StartMenu);
+    } else if step != 0 {
+        entry.change(&mut settings, step);
+    }
+}
+
+fn update_options(
+    options: Res<Options>,
+    settings: Res<Settings>,
+    entries: Query<(&OptionsEntry, &Children)>,
+    spawned: Query<(), Added<OptionsEntry>>,
+    mut texts: Query<&mut Text>,
+) {
+    if !options.is_changed() && !settings.is_changed() && spawned.is_empty() {
+        return;
+    }
+    for (entry, children) in &entries {
+        let label = ENTRIES[entry.0].label(&settings);
+        let selected = entry.0 == options.selected;
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = label.clone();
+            text.sections[0].style.color = if selected { Color::GOLD } else { Color::WHITE };
+        }
+    }
+}
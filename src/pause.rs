@@ -0,0 +1,235 @@
+use bevy::{prelude::*, sprite::Mesh2dHandle, window::WindowFocused};
+
+use crate::{
+    blocks::{
+        drawer::Cell,
+        input::{Action, ActionInput, InputSource},
+        mode::{GameEnd, GameMode},
+    },
+    border::Border,
+    menu::MenuInput,
+    settings::Settings,
+    GameState,
+};
+
+/// Stops the game in [`GameState::Paused`] with a resume/restart/quit menu.
+///
+/// Gameplay only runs in [`GameState::InGame`], so gravity, DAS, lock delay
+/// and the mode clock all hold still while paused. Unless turned off in the
+/// visuals settings the board is hidden meanwhile, so pausing doesn't buy
+/// time to plan. Versus can't be paused, the opponent keeps playing.
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenu>()
+            .add_systems(Update, pause_game.run_if(in_state(GameState::InGame)))
+            .add_systems(OnEnter(GameState::Paused), (spawn_pause_menu, hide_board))
+            .add_systems(
+                Update,
+                (navigate_pause_menu, update_pause_menu)
+                    .chain()
+                    .run_if(in_state(GameState::Paused)),
+            )
+            .add_systems(OnExit(GameState::Paused), (despawn_pause_menu, show_board));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseItem {
+    Resume,
+    Restart,
+    Quit,
+}
+
+const ITEMS: [PauseItem; 3] = [PauseItem::Resume, PauseItem::Restart, PauseItem::Quit];
+
+#[derive(Resource, Default)]
+struct PauseMenu {
+    selected: usize,
+}
+
+#[derive(Component)]
+struct PauseScreen;
+
+#[derive(Component)]
+struct PauseEntry(usize);
+
+fn pause_game(
+    actions: ActionInput,
+    mut focus: EventReader<WindowFocused>,
+    mode: Res<GameMode>,
+    source: Res<InputSource>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // only stop for someone who is actually playing
+    let away = focus.read().any(|x| !x.focused) || tab_hidden();
+    let pause = actions.just_pressed(Action::Pause) || (away && *source == InputSource::Keyboard);
+    if pause && *mode != GameMode::Versus {
+        next_state.set(GameState::Paused);
+    }
+}
+
+/// Whether the page is in a background tab. Browsers may stop running
+/// frames there altogether, so this is checked on every frame that does run.
+#[cfg(target_arch = "wasm32")]
+fn tab_hidden() -> bool {
+    web_sys::window()
+        .and_then(|x| x.document())
+        .is_some_and(|x| x.hidden())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn tab_hidden() -> bool {
+    false
+}
+
+fn spawn_pause_menu(mut commands: Commands, mut menu: ResMut<PauseMenu>, settings: Res<Settings>) {
+    menu.selected = 0;
+    let alpha = if settings.visuals.hide_board_on_pause {
+        0.9
+    } else {
+        0.5
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(12.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., alpha).into(),
+                ..default()
+            },
+            PauseScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "PAUSED",
+                TextStyle {
+                    font_size: 48.,
+                    ..default()
+                },
+            ));
+            for index in 0..ITEMS.len() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::axes(Val::Px(16.), Val::Px(4.)),
+                                ..default()
+                            },
+                            background_color: Color::NONE.into(),
+                            ..default()
+                        },
+                        PauseEntry(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 32.,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn despawn_pause_menu(mut commands: Commands, screen: Query<Entity, With<PauseScreen>>) {
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Blocks of the board, preview and hold, as drawn by the board drawer.
+type BlockMeshes = (With<Mesh2dHandle>, Without<Border>);
+
+fn hide_board(settings: Res<Settings>, mut blocks: Query<&mut Visibility, BlockMeshes>) {
+    if !settings.visuals.hide_board_on_pause {
+        return;
+    }
+    for mut visibility in &mut blocks {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+/// Empty cells stay hidden.
+fn show_board(mut blocks: Query<(&mut Visibility, Option<&Cell>), BlockMeshes>) {
+    for (mut visibility, cell) in &mut blocks {
+        *visibility = cell.map_or(Visibility::Inherited, Cell::visibility);
+    }
+}
+
+fn navigate_pause_menu(
+    input: MenuInput,
+    actions: ActionInput,
+    clicked: Query<(&Interaction, &PauseEntry), Changed<Interaction>>,
+    mut menu: ResMut<PauseMenu>,
+    mut end: ResMut<GameEnd>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let count = ITEMS.len();
+    if input.up() {
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if input.down() {
+        menu.selected = (menu.selected + 1) % count;
+    }
+    let mut confirm = input.confirm();
+    for (interaction, entry) in &clicked {
+        if *interaction == Interaction::Pressed {
+            menu.selected = entry.0;
+            confirm = true;
+        }
+    }
+    // Enter is also bound to pause, picking an entry comes first
+    if !confirm {
+        if actions.just_pressed(Action::Pause) {
+            next_state.set(GameState::InGame);
+        }
+        return;
+    }
+
+    match ITEMS[menu.selected] {
+        PauseItem::Resume => next_state.set(GameState::InGame),
+        PauseItem::Restart => {
+            *end = GameEnd::Restarted;
+            next_state.set(GameState::GameOver);
+        }
+        PauseItem::Quit => {
+            *end = GameEnd::Quit;
+            next_state.set(GameState::GameOver);
+        }
+    }
+}
+
+fn update_pause_menu(
+    menu: Res<PauseMenu>,
+    entries: Query<(&PauseEntry, &Children)>,
+    spawned: Query<(), Added<PauseEntry>>,
+    mut texts: Query<&mut Text>,
+) {
+    if !menu.is_changed() && spawned.is_empty() {
+        return;
+    }
+    for (entry, children) in &entries {
+        let label = match ITEMS[entry.0] {
+            PauseItem::Resume => "Resume",
+            PauseItem::Restart => "Restart",
+            PauseItem::Quit => "Quit to menu",
+        };
+        let selected = entry.0 == menu.selected;
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = label.to_owned();
+            text.sections[0].style.color = if selected { Color::GOLD } else { Color::WHITE };
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use std::{fs, path::PathBuf};
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    blocks::{
+        blocks::{Level, Lines, StartLevel},
+        handling::Handling,
+        input::{InputSource, PlayerInput, TickInput},
+        mode::{GameEnd, GameMode},
+        rng::{use_seed, GameRng},
+    },
+    schedule::InGameSet,
+    GameState,
+};
+
+const HEADER: &str = "tetris-replay 1";
+
+/// A single game: the seed, mode, level and handling it started with, and
+/// every change of input, indexed by simulation tick.
+///
+/// Only the local player's input is stored. Garbage from a versus opponent
+/// couldn't be played back, so versus games aren't recorded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: u64,
+    pub mode: GameMode,
+    pub level: u8,
+    pub handling: Handling,
+    /// Input held from each tick on, until the next entry.
+    pub inputs: Vec<(u32, PlayerInput)>,
+    /// Lines cleared once the game was over, to catch desyncs.
+    /// `None` if it was cut short.
+    pub lines: Option<usize>,
+}
+
+impl Replay {
+    pub const fn new(seed: u64, mode: GameMode, level: u8, handling: Handling) -> Self {
+        Self {
+            seed,
+            mode,
+            level,
+            handling,
+            inputs: Vec::new(),
+            lines: None,
+        }
+    }
+
+    pub fn input_at(&self, tick: u32) -> PlayerInput {
+        let index = self.inputs.partition_point(|(x, _)| *x <= tick);
+        index
+            .checked_sub(1)
+            .map_or_else(PlayerInput::default, |x| self.inputs[x].1)
+    }
+
+    fn push(&mut self, tick: u32, input: PlayerInput) {
+        let last = self.inputs.last().map_or_else(PlayerInput::default, |x| x.1);
+        if input != last {
+            self.inputs.push((tick, input));
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut text = format!(
+            "{HEADER}\nseed {}\nmode {}\nlevel {}\nhandling {}\n",
+            self.seed,
+            self.mode.name(),
+            self.level,
+            self.handling.encode()
+        );
+        for (tick, input) in &self.inputs {
+            text += &format!("input {tick} {}\n", input.0);
+        }
+        if let Some(lines) = self.lines {
+            text += &format!("lines {lines}\n");
+        }
+        text
+    }
+
+    pub fn decode(text: &str) -> Option<Self> {
+        let mut lines = text.lines().map(str::trim).filter(|x| !x.is_empty());
+        if lines.next()? != HEADER {
+            return None;
+        }
+        // replays from before the mode and level were recorded all started
+        // at the default ones
+        let mut replay = Self {
+            level: StartLevel::default().0,
+            ..Self::default()
+        };
+        for line in lines {
+            let (kind, rest) = line.split_once(' ')?;
+            match kind {
+                "seed" => replay.seed = rest.parse().ok()?,
+                "mode" => replay.mode = GameMode::from_name(rest)?,
+                "level" => replay.level = rest.parse().ok()?,
+                "handling" => replay.handling = Handling::decode(rest)?,
+                "input" => {
+                    let (tick, input) = rest.split_once(' ')?;
+                    replay
+                        .inputs
+                        .push((tick.parse().ok()?, PlayerInput(input.parse().ok()?)));
+                }
+                "lines" => replay.lines = Some(rest.parse().ok()?),
+                _ => return None,
+            }
+        }
+        Some(replay)
+    }
+}
+
+/// Saves every game but versus ones to `<dir>/<seed>.replay` once it's
+/// over, or when the app is closed mid-game.
+pub struct RecordPlugin {
+    pub dir: PathBuf,
+}
+
+impl Plugin for RecordPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Recorder {
+            dir: self.dir.clone(),
+            tick: 0,
+            replay: None,
+        })
+        .add_systems(
+            FixedUpdate,
+            record_input
+                .after(InGameSet::UserInput)
+                .before(InGameSet::BoardInitUpdate)
+                .run_if(in_state(GameState::InGame))
+                .run_if(not(resource_equals(GameMode::Versus))),
+        )
+        .add_systems(OnEnter(GameState::GameOver), finish_recording)
+        .add_systems(Last, save_on_exit);
+    }
+}
+
+#[derive(Resource)]
+struct Recorder {
+    dir: PathBuf,
+    tick: u32,
+    replay: Option<Replay>,
+}
+
+impl Recorder {
+    fn save(&mut self, lines: Option<usize>) {
+        self.tick = 0;
+        let Some(mut replay) = self.replay.take() else {
+            return;
+        };
+        replay.lines = lines;
+        let path = self.dir.join(format!("{}.replay", replay.seed));
+        match fs::create_dir_all(&self.dir).and_then(|()| fs::write(&path, replay.encode())) {
+            Ok(()) => info!("replay saved to {}", path.display()),
+            Err(err) => error!("could not save replay to {}: {err}", path.display()),
+        }
+    }
+}
+
+fn record_input(
+    mut recorder: ResMut<Recorder>,
+    input: Res<TickInput>,
+    rng: Res<GameRng>,
+    mode: Res<GameMode>,
+    start_level: Res<StartLevel>,
+    handling: Res<Handling>,
+) {
+    let tick = recorder.tick;
+    recorder
+        .replay
+        .get_or_insert_with(|| Replay::new(rng.seed(), *mode, start_level.0, *handling))
+        .push(tick, input.current());
+    recorder.tick += 1;
+}
+
+fn finish_recording(mut recorder: ResMut<Recorder>, lines: Res<Lines>, end: Res<GameEnd>) {
+    // an abandoned game has no result to check a playback against
+    recorder.save((!end.abandoned()).then(|| lines.total()));
+}
+
+fn save_on_exit(mut recorder: ResMut<Recorder>, exit: EventReader<AppExit>) {
+    if !exit.is_empty() {
+        recorder.save(None);
+    }
+}
+
+/// Plays a recorded game back in place of the keyboard, and quits once
+/// it's over.
+pub struct PlaybackPlugin {
+    pub path: PathBuf,
+}
+
+impl Plugin for PlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        let replay = match fs::read_to_string(&self.path) {
+            Ok(text) => Replay::decode(&text),
+            Err(err) => {
+                error!("could not read replay {}: {err}", self.path.display());
+                return;
+            }
+        };
+        let Some(replay) = replay else {
+            error!("{} is not a replay", self.path.display());
+            return;
+        };
+
+        use_seed(&mut app.world, replay.seed);
+        app.insert_resource(InputSource::External)
+            .insert_resource(replay.mode)
+            .insert_resource(StartLevel(replay.level))
+            .insert_resource(Level(replay.level))
+            .insert_resource(replay.handling)
+            .insert_resource(Playback { replay, tick: 0 })
+            .add_systems(FixedUpdate, play_input.in_set(InGameSet::UserInput))
+            .add_systems(OnEnter(GameState::GameOver), finish_playback);
+    }
+}
+
+#[derive(Resource)]
+struct Playback {
+    replay: Replay,
+    tick: u32,
+}
+
+fn play_input(mut playback: ResMut<Playback>, mut input: ResMut<TickInput>) {
+    input.advance(playback.replay.input_at(playback.tick));
+    playback.tick += 1;
+}
+
+fn finish_playback(
+    playback: Res<Playback>,
+    lines: Res<Lines>,
+    end: Res<GameEnd>,
+    mut exit: EventWriter<AppExit>,
+) {
+    match playback.replay.lines {
+        Some(expected) if expected != lines.total() && !end.abandoned() => warn!(
+            "replay desynced, ended with {} lines instead of {expected}",
+            lines.total()
+        ),
+        _ => info!("replay finished after {} ticks", playback.tick),
+    }
+    exit.send(AppExit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blocks::blocks::{Board, Score, TetrisSimulationPlugin},
+        bot::{BotDifficulty, BotPlugin},
+        schedule::SchedulePlugin,
+    };
+
+    fn simulation() -> App {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .add_state::<GameState>()
+            .insert_resource(State::new(GameState::InGame))
+            .add_plugins((SchedulePlugin, TetrisSimulationPlugin));
+        app
+    }
+
+    /// Runs `ticks` ticks, or until the game is over. Returns the ticks run.
+    fn run(app: &mut App, ticks: u32) -> u32 {
+        for tick in 0..ticks {
+            app.world
+                .resource_mut::<Time>()
+                .advance_by(std::time::Duration::from_secs_f64(1. / 60.));
+            app.world.run_schedule(First);
+            app.world.run_schedule(FixedUpdate);
+            app.world.run_schedule(StateTransition);
+            if *app.world.resource::<State<GameState>>() == GameState::GameOver {
+                return tick + 1;
+            }
+        }
+        ticks
+    }
+
+    fn outcome(app: &App) -> (String, usize, usize) {
+        (
+            app.world.resource::<Board>().to_string(),
+            app.world.resource::<Lines>().total(),
+            app.world.resource::<Score>().value(),
+        )
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let mut replay = Replay::new(
+            u64::MAX,
+            GameMode::Sprint,
+            7,
+            Handling {
+                das: 12,
+                arr: 0,
+                ..Handling::default()
+            },
+        );
+        replay.push(0, PlayerInput::LEFT);
+        replay.push(3, PlayerInput::LEFT);
+        replay.push(
+            5,
+            PlayerInput(PlayerInput::HARD_DROP.0 | PlayerInput::ROTATE_CW.0),
+        );
+        replay.push(6, PlayerInput::default());
+        replay.lines = Some(40);
+        assert_eq!(replay.inputs.len(), 3);
+        assert_eq!(Replay::decode(&replay.encode()), Some(replay.clone()));
+        assert_eq!(replay.input_at(4), PlayerInput::LEFT);
+        assert_eq!(Replay::decode("not a replay"), None);
+    }
+
+    #[test]
+    fn playback_ends_up_where_the_recording_did() {
+        let dir = std::env::temp_dir().join(format!("tetris-replay-{}", std::process::id()));
+        let mut recording = simulation();
+        recording.add_plugins((
+            BotPlugin {
+                difficulty: BotDifficulty {
+                    lookahead: 0,
+                    ..BotDifficulty::HARD
+                },
+            },
+            RecordPlugin { dir: dir.clone() },
+        ));
+        use_seed(&mut recording.world, 7);
+        let ticks = run(&mut recording, 1500);
+        let recorded = outcome(&recording);
+        assert!(recorded.1 > 0, "the bot cleared no lines");
+        recording.world.resource_mut::<Recorder>().save(None);
+
+        let mut playback = simulation();
+        playback.add_plugins(PlaybackPlugin {
+            path: dir.join("7.replay"),
+        });
+        assert_eq!(run(&mut playback, ticks), ticks);
+        assert_eq!(outcome(&playback), recorded);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,315 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+
+use crate::{
+    blocks::{
+        blocks::{Block, Board, Level, Lines, Links, Score, POINT_SIZE},
+        input::InputSource,
+        mode::{GameClock, GameEnd, GameMode, SPRINT_LINES},
+        rng::GameRng,
+        skin::{BlockAssets, Mino},
+        stats::Stats,
+    },
+    highscores::{enter_name, HighScores, PendingRecord},
+    menu::MenuInput,
+    settings::Settings,
+    GameState,
+};
+
+/// Seconds per row of the game over fill.
+const FILL_STEP: f32 = 0.05;
+
+/// Shows how the game went in [`GameState::GameOver`], next to the final
+/// board, with the name prompt for a new record and a retry/menu choice.
+///
+/// The next game is only set up once the results are left. Abandoned games
+/// skip them, and so do bots, which just play on.
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ResultsMenu>()
+            .add_systems(OnEnter(GameState::GameOver), spawn_results)
+            .add_systems(
+                Update,
+                (
+                    navigate_results.before(enter_name),
+                    update_results,
+                    fill_board.run_if(|settings: Res<Settings>| settings.visuals.game_over_fill),
+                )
+                    .run_if(in_state(GameState::GameOver))
+                    .run_if(any_with_component::<ResultsScreen>()),
+            )
+            .add_systems(OnExit(GameState::GameOver), despawn_results);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResultsItem {
+    Retry,
+    Menu,
+}
+
+const ITEMS: [ResultsItem; 2] = [ResultsItem::Retry, ResultsItem::Menu];
+
+#[derive(Resource, Default)]
+struct ResultsMenu {
+    selected: usize,
+    /// Rows of the board filled in so far.
+    filled: usize,
+    fill_timer: Timer,
+}
+
+#[derive(Component)]
+struct ResultsScreen;
+
+#[derive(Component)]
+struct ResultsText;
+
+/// A square of the game over fill.
+#[derive(Component)]
+struct FillSquare;
+
+#[derive(Component)]
+struct ResultsEntry(usize);
+
+fn spawn_results(
+    mut commands: Commands,
+    end: Res<GameEnd>,
+    source: Res<InputSource>,
+    mode: Res<GameMode>,
+    mut menu: ResMut<ResultsMenu>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    match *end {
+        GameEnd::Restarted => return next_state.set(GameState::InGame),
+        GameEnd::Quit => return next_state.set(GameState::StartMenu),
+        _ if *source == InputSource::External => return next_state.set(GameState::InGame),
+        _ => {}
+    }
+    *menu = ResultsMenu {
+        fill_timer: Timer::from_seconds(FILL_STEP, TimerMode::Repeating),
+        ..default()
+    };
+    // the lobby starts the next round of versus, there's nothing to pick
+    let items = if *mode == GameMode::Versus {
+        0
+    } else {
+        ITEMS.len()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(33.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    padding: UiRect::all(Val::Px(24.)),
+                    row_gap: Val::Px(12.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.8).into(),
+                ..default()
+            },
+            ResultsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.,
+                        ..default()
+                    },
+                ),
+                ResultsText,
+            ));
+            for index in 0..items {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::axes(Val::Px(16.), Val::Px(4.)),
+                                ..default()
+                            },
+                            background_color: Color::NONE.into(),
+                            ..default()
+                        },
+                        ResultsEntry(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 32.,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn despawn_results(
+    mut commands: Commands,
+    screen: Query<Entity, With<ResultsScreen>>,
+    fill: Query<Entity, With<FillSquare>>,
+) {
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+    for entity in &fill {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Waits for the name of a new record first, so the Enter that saves it
+/// doesn't also pick an entry.
+fn navigate_results(
+    input: MenuInput,
+    clicked: Query<(&Interaction, &ResultsEntry), Changed<Interaction>>,
+    entries: Query<(), With<ResultsEntry>>,
+    pending: Res<PendingRecord>,
+    mut menu: ResMut<ResultsMenu>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let count = entries.iter().count();
+    if pending.is_pending() || count == 0 {
+        return;
+    }
+    if input.up() {
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if input.down() {
+        menu.selected = (menu.selected + 1) % count;
+    }
+    let mut confirm = input.confirm();
+    for (interaction, entry) in &clicked {
+        if *interaction == Interaction::Pressed {
+            menu.selected = entry.0;
+            confirm = true;
+        }
+    }
+    if !confirm {
+        return;
+    }
+    match ITEMS[menu.selected] {
+        ResultsItem::Retry => next_state.set(GameState::InGame),
+        ResultsItem::Menu => next_state.set(GameState::StartMenu),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_results(
+    mode: Res<GameMode>,
+    end: Res<GameEnd>,
+    score: Res<Score>,
+    lines: Res<Lines>,
+    level: Res<Level>,
+    clock: Res<GameClock>,
+    stats: Res<Stats>,
+    rng: Res<GameRng>,
+    pending: Res<PendingRecord>,
+    scores: Res<HighScores>,
+    menu: Res<ResultsMenu>,
+    mut text: Query<&mut Text, With<ResultsText>>,
+    entries: Query<(&ResultsEntry, &Children)>,
+    mut labels: Query<&mut Text, Without<ResultsText>>,
+) {
+    let reason = match (*end, *mode) {
+        (GameEnd::Finished, GameMode::Sprint) => format!("{SPRINT_LINES} lines cleared"),
+        (GameEnd::Finished, GameMode::Ultra) => String::from("Time's up"),
+        (GameEnd::Finished, _) => String::from("Finished"),
+        _ => String::from("Topped out"),
+    };
+    let placed = Block::ALL
+        .iter()
+        .map(|kind| {
+            format!(
+                "{kind} {}",
+                stats.placed.get(kind).copied().unwrap_or_default()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("  ");
+    let mut value = format!(
+        "{} - {reason}\n\nscore: {}\nlines: {}\nlevel: {}\n{}\n{placed}\nseed: {}\n",
+        mode.name(),
+        score.value(),
+        lines.total(),
+        level.0,
+        stats.summary(&clock),
+        rng.seed(),
+    );
+    if let Some(prompt) = pending.prompt(&scores) {
+        value += &format!("\n{prompt}\n");
+    } else if *mode == GameMode::Versus {
+        value += "\nWaiting for the next round\n";
+    }
+    for mut text in &mut text {
+        text.sections[0].value = value.clone();
+    }
+
+    for (entry, children) in &entries {
+        let label = match ITEMS[entry.0] {
+            ResultsItem::Retry => "Retry",
+            ResultsItem::Menu => "Menu",
+        };
+        // nothing to pick while a name is being entered
+        let color = if pending.is_pending() {
+            Color::GRAY
+        } else if entry.0 == menu.selected {
+            Color::GOLD
+        } else {
+            Color::WHITE
+        };
+        let mut labels = labels.iter_many_mut(children);
+        while let Some(mut text) = labels.fetch_next() {
+            text.sections[0].value = label.to_owned();
+            text.sections[0].style.color = color;
+        }
+    }
+}
+
+/// Greys out the board from the bottom up, a row per [`FILL_STEP`], with
+/// squares laid over it. The board itself is left as the game ended.
+fn fill_board(
+    time: Res<Time>,
+    mut menu: ResMut<ResultsMenu>,
+    board: Res<Board>,
+    assets: Res<BlockAssets>,
+    mut commands: Commands,
+) {
+    let rows = board.inner.len();
+    if menu.filled >= rows {
+        return;
+    }
+    let steps = menu
+        .fill_timer
+        .tick(time.delta())
+        .times_finished_this_tick() as usize;
+    let (mesh, material) = assets.look(Mino::Garbage, Links::ALL);
+    for _ in 0..steps.min(rows - menu.filled) {
+        let row = rows - 1 - menu.filled;
+        for col in 0..board.inner[row].len() {
+            let transform = Transform::default()
+                .with_scale(Vec3::splat(POINT_SIZE))
+                .with_translation(Vec3::new(
+                    POINT_SIZE * col as f32 - POINT_SIZE * 4.,
+                    -POINT_SIZE * row as f32 + POINT_SIZE * 10.,
+                    // over the board's own squares
+                    1.,
+                ));
+            commands.spawn((
+                MaterialMesh2dBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform,
+                    ..default()
+                },
+                FillSquare,
+            ));
+        }
+        menu.filled += 1;
+    }
+}
@@ -1,6 +1,17 @@
 use bevy::prelude::*;
 
-use crate::{blocks::blocks::Board, GameState};
+use crate::{
+    blocks::blocks::{Board, LineClear},
+    GameState,
+};
+
+/// Default simulation ticks per second. Gameplay runs in `FixedUpdate` at
+/// this rate and counts all its timers in ticks, drawing stays in `Update`.
+///
+/// Like running a NES at PAL speed, a different rate (`--tick-rate`) makes
+/// the whole game faster or slower, but never changes its outcome.
+pub const TICK_RATE: f64 = 60.0;
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
 pub enum InGameSet {
     UserInput,
@@ -14,27 +25,29 @@ pub struct SchedulePlugin;
 
 impl Plugin for SchedulePlugin {
     fn build(&self, app: &mut App) {
-        app.configure_sets(
-            Update,
-            (
-                InGameSet::BoardInitUpdate,
-                InGameSet::UserInput,
-                InGameSet::EntityMovement,
-                InGameSet::BoardDrawer,
-                InGameSet::InfoUpdate,
+        app.insert_resource(Time::<Fixed>::from_hz(TICK_RATE))
+            .configure_sets(
+                FixedUpdate,
+                (
+                    InGameSet::UserInput,
+                    InGameSet::BoardInitUpdate,
+                    InGameSet::EntityMovement,
+                    InGameSet::InfoUpdate,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .configure_sets(
+                Update,
+                (InGameSet::BoardDrawer)
+                    .run_if(in_state(GameState::InGame).or_else(in_state(GameState::GameOver)))
+                    .run_if(|x: Res<Board>, y: Res<LineClear>| x.is_changed() || y.is_changed()),
             )
-                .chain()
-                .run_if(in_state(GameState::InGame)),
-        )
-        .configure_sets(
-            Update,
-            (InGameSet::BoardDrawer).run_if(|x: Res<Board>| x.is_changed()),
-        )
-        .add_systems(
-            Update,
-            apply_deferred
-                .after(InGameSet::BoardDrawer)
-                .before(InGameSet::BoardInitUpdate),
-        );
+            .add_systems(
+                FixedUpdate,
+                apply_deferred
+                    .after(InGameSet::BoardInitUpdate)
+                    .before(InGameSet::EntityMovement),
+            );
     }
 }
@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    blocks::{
+        blocks::{Level, StartLevel},
+        drawer::LineClearStyle,
+        handling::Handling,
+        input::{GamepadBindings, KeyBindings},
+        mode::GameMode,
+        rng::{seed_text, use_seed},
+    },
+    storage,
+};
+
+/// Everything the player can configure, kept in `settings.toml` in the
+/// platform config directory, or in local storage in the browser.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Format the settings were saved with, `0` for files from before it
+    /// was recorded.
+    #[serde(default)]
+    pub version: u32,
+    pub handling: Handling,
+    pub controls: KeyBindings,
+    pub gamepad: GamepadBindings,
+    pub visuals: Visuals,
+    pub game: GameDefaults,
+    pub training: Training,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            handling: Handling::default(),
+            controls: KeyBindings::default(),
+            gamepad: GamepadBindings::default(),
+            visuals: Visuals::default(),
+            game: GameDefaults::default(),
+            training: Training::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Visuals {
+    /// Print the board to the terminal whenever it changes, for debugging.
+    pub terminal_board: bool,
+    /// Hide the board, preview and hold while paused, so a pause can't be
+    /// used to plan ahead.
+    pub hide_board_on_pause: bool,
+    /// Grey out the final board row by row on game over.
+    pub game_over_fill: bool,
+    /// Show PPS, KPP, APM and the line clears next to the board.
+    pub stats_panel: bool,
+    /// Count the pieces dealt by kind, and the pieces since the last I.
+    pub piece_panel: bool,
+    /// Sprite sheet to draw the minos with, `assets/skins/<skin>.png`. Flat
+    /// colours without one.
+    pub skin: Option<String>,
+    /// Draw each piece as one shape, with gaps between pieces, even once a
+    /// line clear splits one.
+    pub connected: bool,
+    /// How full rows go while `handling.line_clear_delay` holds them.
+    pub line_clear: LineClearStyle,
+}
+
+impl Default for Visuals {
+    fn default() -> Self {
+        Self {
+            terminal_board: false,
+            hide_board_on_pause: true,
+            game_over_fill: true,
+            stats_panel: false,
+            piece_panel: false,
+            skin: None,
+            connected: false,
+            line_clear: LineClearStyle::default(),
+        }
+    }
+}
+
+/// How a new game starts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameDefaults {
+    pub mode: GameMode,
+    pub level: u8,
+    /// Seed of the first game, the following ones derive theirs from it.
+    /// A random one if unset. `--seed` takes precedence.
+    #[serde(with = "seed_text::option", skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl Default for GameDefaults {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::default(),
+            level: StartLevel::default().0,
+            seed: None,
+        }
+    }
+}
+
+/// Practice aids, see [`FinessePlugin`](crate::blocks::finesse::FinessePlugin).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Training {
+    /// Show the fewest presses for each piece placed.
+    pub finesse_trainer: bool,
+    /// Put a piece placed with too many presses back at the top.
+    pub finesse_retry: bool,
+}
+
+impl Settings {
+    /// Bumped whenever a field changes meaning, so [`Self::migrate`] can
+    /// convert older files. Added fields just take their defaults.
+    pub const VERSION: u32 = 2;
+
+    const FILE: &'static str = "settings.toml";
+
+    /// Settings saved last time, or the defaults if there are none or they
+    /// can't be read.
+    pub fn load() -> Self {
+        Self::load_from(Self::FILE)
+    }
+
+    /// Unreadable files, and files from a newer version that this one
+    /// would only partly keep, are set aside so the next save doesn't
+    /// lose them.
+    fn load_from(file: &str) -> Self {
+        let Some(text) = storage::read(file) else {
+            return Self::default();
+        };
+        match Self::parse(&text) {
+            Ok((settings, version)) => {
+                if version > Self::VERSION {
+                    warn!(
+                        "settings are from a newer version ({version} > {}), unknown ones are dropped",
+                        Self::VERSION
+                    );
+                    storage::set_aside(file);
+                }
+                settings
+            }
+            Err(err) => {
+                warn!("ignoring unreadable settings: {err}");
+                storage::set_aside(file);
+                Self::default()
+            }
+        }
+    }
+
+    /// The settings in `text`, migrated, and the version they were saved
+    /// with.
+    fn parse(text: &str) -> Result<(Self, u32), toml::de::Error> {
+        let mut table: toml::Table = toml::from_str(text)?;
+        let version = match table.get("version") {
+            Some(version) => u32::deserialize(version.clone())?,
+            None => 0,
+        };
+        Self::migrate(&mut table, version);
+        let mut settings = Self::deserialize(table)?;
+        settings.version = Self::VERSION;
+        Ok((settings, version))
+    }
+
+    /// Brings a file saved with an older `version` up to date before it's
+    /// read. Version 0 only lacked the version itself.
+    fn migrate(table: &mut toml::Table, version: u32) {
+        if version < 2 {
+            // printing the board used to be on by default, so every file
+            // saved it, whether the player wanted it or not
+            if let Some(toml::Value::Table(visuals)) = table.get_mut("visuals") {
+                visuals.remove("terminal_board");
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        match toml::to_string_pretty(self) {
+            Ok(text) => storage::write(Self::FILE, &text),
+            Err(err) => error!("could not encode settings: {err}"),
+        }
+    }
+}
+
+/// Loads [`Settings`] on startup and saves them whenever they change.
+///
+/// Gameplay reads its own copies (e.g. [`Handling`]), so changed settings
+/// only take effect where they are applied explicitly.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = Settings::load();
+        if let Some(seed) = settings.game.seed {
+            use_seed(&mut app.world, seed);
+        }
+        app.insert_resource(settings.handling)
+            .insert_resource(settings.controls.clone())
+            .insert_resource(settings.gamepad.clone())
+            .insert_resource(settings.game.mode)
+            .insert_resource(StartLevel(settings.game.level))
+            .insert_resource(Level(settings.game.level))
+            .insert_resource(settings)
+            .add_systems(
+                Update,
+                save_settings.run_if(resource_changed::<Settings>()),
+            );
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    settings.save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::input::Action;
+
+    #[test]
+    fn seed_round_trips_and_is_left_out_when_unset() {
+        let text = toml::to_string_pretty(&Settings::default()).unwrap();
+        assert!(!text.contains("seed"));
+
+        let mut settings = Settings::default();
+        settings.game.seed = Some(u64::MAX);
+        let text = toml::to_string_pretty(&settings).unwrap();
+        assert_eq!(toml::from_str::<Settings>(&text).unwrap(), settings);
+
+        let loaded: Settings = toml::from_str("[game]\nseed = 1234").unwrap();
+        assert_eq!(loaded.game.seed, Some(1234));
+    }
+
+    #[test]
+    fn older_files_stop_printing_the_board() {
+        let text = "version = 1\n[visuals]\nterminal_board = true\nconnected = true";
+        let (settings, _) = Settings::parse(text).unwrap();
+        assert!(!settings.visuals.terminal_board);
+        assert!(settings.visuals.connected);
+        assert_eq!(settings.version, Settings::VERSION);
+
+        let mut on = Settings::default();
+        on.visuals.terminal_board = true;
+        let text = toml::to_string_pretty(&on).unwrap();
+        assert_eq!(Settings::parse(&text).unwrap(), (on, Settings::VERSION));
+    }
+
+    #[test]
+    fn corrupt_file_falls_back_to_the_defaults_and_is_kept() {
+        let text = "[handling\ndas = 10";
+        storage::write("corrupt.toml", text);
+        assert_eq!(Settings::load_from("corrupt.toml"), Settings::default());
+        assert_eq!(storage::read("corrupt.toml"), None);
+        assert_eq!(storage::read("corrupt.toml.bak").as_deref(), Some(text));
+    }
+
+    #[test]
+    fn version_0_file_loads() {
+        // saved before there was a version, or anything but these three
+        let text = "[handling]\ndas = 7\narr = 1\n\n[controls]\nHold = [\"C\"]\n\n[gamepad]\nstick_deadzone = 0.25";
+        storage::write("version_0.toml", text);
+        let settings = Settings::load_from("version_0.toml");
+        assert_eq!(settings.version, Settings::VERSION);
+        assert_eq!((settings.handling.das, settings.handling.arr), (7, 1));
+        assert_eq!(settings.controls.0[&Action::Hold], vec![KeyCode::C]);
+        assert_eq!(settings.gamepad.stick_deadzone, 0.25);
+        assert_eq!(settings.visuals, Visuals::default());
+        assert_eq!(storage::read("version_0.toml.bak"), None);
+    }
+
+    #[test]
+    fn newer_file_keeps_what_it_can_and_is_set_aside() {
+        let text = "version = 99\nfrom_the_future = true\n\n[handling]\ndas = 4";
+        storage::write("newer.toml", text);
+        let settings = Settings::load_from("newer.toml");
+        assert_eq!(settings.version, Settings::VERSION);
+        assert_eq!(settings.handling.das, 4);
+        assert_eq!(storage::read("newer.toml.bak").as_deref(), Some(text));
+    }
+}
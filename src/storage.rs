@@ -0,0 +1,91 @@
+//! Small text files kept between runs (settings, high scores): in the
+//! platform config directory on native, in local storage in the browser.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod imp {
+    use std::{fs, path::PathBuf};
+
+    use bevy::prelude::*;
+
+    fn path(name: &str) -> Option<PathBuf> {
+        // tests never touch the player's files
+        if cfg!(test) {
+            let dir = format!("tetris-with-bevy-test-{}", std::process::id());
+            return Some(std::env::temp_dir().join(dir).join(name));
+        }
+        Some(dirs::config_dir()?.join("tetris-with-bevy").join(name))
+    }
+
+    pub fn read(name: &str) -> Option<String> {
+        fs::read_to_string(path(name)?).ok()
+    }
+
+    pub fn write(name: &str, text: &str) {
+        let Some(path) = path(name) else {
+            return;
+        };
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, text));
+        if let Err(err) = written {
+            error!("could not save {}: {err}", path.display());
+        }
+    }
+
+    /// Keeps a file that can't be used as is around as `<name>.bak` instead
+    /// of overwriting it on the next save.
+    pub fn set_aside(name: &str) {
+        let Some(path) = path(name) else {
+            return;
+        };
+        let backup = path.with_file_name(format!("{name}.bak"));
+        match fs::rename(&path, &backup) {
+            Ok(()) => warn!("moved {name} aside to {}", backup.display()),
+            Err(err) => error!("could not move {} aside: {err}", path.display()),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod imp {
+    use bevy::prelude::*;
+    use web_sys::Storage;
+
+    fn local_storage() -> Option<Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    fn key(name: &str) -> String {
+        format!("tetris-with-bevy.{name}")
+    }
+
+    pub fn read(name: &str) -> Option<String> {
+        local_storage()?.get_item(&key(name)).ok()?
+    }
+
+    pub fn write(name: &str, text: &str) {
+        let Some(storage) = local_storage() else {
+            return;
+        };
+        if let Err(err) = storage.set_item(&key(name), text) {
+            error!("could not save {name} to local storage: {err:?}");
+        }
+    }
+
+    /// Keeps data that can't be used as is around under `<name>.bak`
+    /// instead of overwriting it on the next save.
+    pub fn set_aside(name: &str) {
+        let Some(storage) = local_storage() else {
+            return;
+        };
+        if let Ok(Some(text)) = storage.get_item(&key(name)) {
+            let backup = key(&format!("{name}.bak"));
+            let _ = storage.set_item(&backup, &text);
+            let _ = storage.remove_item(&key(name));
+            warn!("moved {name} aside to {backup} in local storage");
+        }
+    }
+}
+
+pub use imp::{read, set_aside, write};
@@ -0,0 +1,332 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{channel, Receiver},
+        Mutex,
+    },
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    blocks::{
+        blocks::{Block, BlockState, Board, BoardBlockState, CurrentBlockWithPreview, PREVIEW_COUNT},
+        input::{InputSource, PlayerInput, TickInput},
+    },
+    bot::{search::placements, PieceDriver},
+    schedule::InGameSet,
+};
+
+use self::messages::{BotMessage, FrontendMessage, Move, Piece};
+
+pub use tetris::tbp_messages as messages;
+
+/// Rows the protocol always sends, our board is just the bottom of it.
+const TBP_ROWS: usize = 40;
+/// Ticks to wait after each button press.
+const ACTION_INTERVAL: u32 = 2;
+
+/// Lets an external bot play through the Tetris Bot Protocol, e.g.
+/// `cargo run -- --tbp "cargo run --bin tbp_stub"`.
+///
+/// The bot runs as a child process speaking JSON lines over stdin/stdout.
+/// Its suggested moves are matched against placements reachable with this
+/// game's rotation rules, and then performed through [`TickInput`] like
+/// the built-in bot does. Whenever the board ends up different from what
+/// the bot was told, it's restarted with the actual state.
+///
+/// The frontend doesn't hold for the bot: it's told there's no hold piece,
+/// and suggestions for any other piece than the current one are skipped.
+pub struct TbpPlugin {
+    pub command: String,
+}
+
+impl Plugin for TbpPlugin {
+    fn build(&self, app: &mut App) {
+        let mut args = self.command.split_whitespace();
+        let Some(program) = args.next() else {
+            error!("no tbp bot command given");
+            return;
+        };
+        let mut child = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                error!("could not start tbp bot `{}`: {err}", self.command);
+                return;
+            }
+        };
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            error!("could not talk to tbp bot `{}`", self.command);
+            return;
+        };
+        let (sender, incoming) = channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                match serde_json::from_str(&line) {
+                    Ok(message) => {
+                        if sender.send(message).is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => warn!("ignoring malformed tbp message {line}: {err}"),
+                }
+            }
+        });
+
+        app.insert_resource(InputSource::External)
+            .insert_resource(TbpBot {
+                stdin,
+                incoming: Mutex::new(incoming),
+                ready: false,
+                started: false,
+                requested: 0,
+                suggestion: None,
+                piece: None,
+                moves: Vec::new(),
+                expected: None,
+                driver: PieceDriver::default(),
+            })
+            .add_systems(Update, receive_messages)
+            .add_systems(FixedUpdate, drive_tbp.in_set(InGameSet::UserInput));
+    }
+}
+
+#[derive(Resource)]
+struct TbpBot {
+    stdin: ChildStdin,
+    incoming: Mutex<Receiver<BotMessage>>,
+    /// The bot accepted our rules.
+    ready: bool,
+    /// The bot was sent a `start` and keeps track of the game itself.
+    started: bool,
+    /// Suggestions asked for but not received yet.
+    requested: usize,
+    /// Latest suggestion, waiting to be picked up by `drive_tbp`.
+    suggestion: Option<Vec<Move>>,
+    /// Falling piece the bot was asked about.
+    piece: Option<Entity>,
+    /// Moves suggested for `piece`.
+    moves: Vec<Move>,
+    /// Board the bot expects once `piece` locks, as sent over the wire.
+    expected: Option<Vec<Vec<Option<char>>>>,
+    driver: PieceDriver,
+}
+
+impl TbpBot {
+    fn send(&mut self, message: &FrontendMessage) {
+        let line = match serde_json::to_string(message) {
+            Ok(line) => line,
+            Err(err) => {
+                error!("could not encode {message:?}: {err}");
+                return;
+            }
+        };
+        if let Err(err) = writeln!(self.stdin, "{line}") {
+            warn!("tbp bot went away: {err}");
+        }
+    }
+
+    /// Tells the bot about a newly spawned piece, restarting it with the
+    /// current state if the last placement didn't go as it expected.
+    fn sync(&mut self, board: &Board, block: Block, preview: &[Block]) {
+        let cells = tbp_board(board);
+        if self.started && self.expected.as_ref() == Some(&cells) {
+            if let Some(&piece) = preview.last() {
+                self.send(&FrontendMessage::NewPiece {
+                    piece: piece.into(),
+                });
+            }
+        } else {
+            if self.started {
+                self.send(&FrontendMessage::Stop);
+            }
+            let queue = std::iter::once(block)
+                .chain(preview.iter().copied())
+                .map(Piece::from)
+                .collect();
+            self.send(&FrontendMessage::Start {
+                hold: None,
+                queue,
+                combo: 0,
+                back_to_back: false,
+                board: cells,
+            });
+            self.started = true;
+        }
+        self.expected = None;
+    }
+
+    /// Picks the first suggested move this game can actually perform, or
+    /// failing that, whatever placement overlaps the best one the most.
+    /// Returns the move if it was an exact match.
+    fn pick(&mut self, board: &Board, block: Block) -> Option<Move> {
+        let options = placements(board, block, true);
+        let cells = |mv: &Move| board_cells(board, mv);
+        // anything else would need a hold first
+        self.moves
+            .retain(|mv| mv.location.piece == Piece::from(block));
+        let exact = self.moves.iter().find_map(|mv| {
+            let cells = cells(mv);
+            options.iter().find(|x| x.cells == cells).map(|x| (*mv, x))
+        });
+        if let Some((mv, placement)) = exact {
+            self.driver.target = placement.cells.clone();
+            self.expected = Some(tbp_board(&placement.board));
+            return Some(mv);
+        }
+        let closest = self.moves.first().and_then(|mv| {
+            let cells = cells(mv);
+            options
+                .iter()
+                .max_by_key(|x| x.cells.iter().filter(|x| cells.contains(x)).count())
+        });
+        self.driver.target = closest.map(|x| x.cells.clone()).unwrap_or_default();
+        None
+    }
+}
+
+impl Drop for TbpBot {
+    fn drop(&mut self) {
+        self.send(&FrontendMessage::Quit);
+    }
+}
+
+impl From<Block> for Piece {
+    fn from(block: Block) -> Self {
+        match block {
+            Block::T => Self::T,
+            Block::J => Self::J,
+            Block::L => Self::L,
+            Block::I => Self::I,
+            Block::O => Self::O,
+            Block::S => Self::S,
+            Block::Z => Self::Z,
+        }
+    }
+}
+
+/// The board the way the protocol has it: bottom row first, padded to
+/// [`TBP_ROWS`], without the falling piece.
+fn tbp_board(board: &Board) -> Vec<Vec<Option<char>>> {
+    let cols = board.inner[0].len();
+    let mut rows = board
+        .inner
+        .iter()
+        .rev()
+        .map(|row| {
+            row.iter()
+                .map(|x| match x {
+                    BoardBlockState::Placed { block_type, .. } => {
+                        Some(Piece::from(*block_type).letter())
+                    }
+                    BoardBlockState::Garbage => Some('G'),
+                    BoardBlockState::Falling { .. } | BoardBlockState::Empty => None,
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>();
+    rows.resize(TBP_ROWS.max(rows.len()), vec![None; cols]);
+    rows
+}
+
+/// Our `(row, col)` cells covered by a move, sorted like
+/// [`falling_cells`](crate::bot::search::falling_cells) returns them.
+fn board_cells(board: &Board, mv: &Move) -> Vec<(usize, usize)> {
+    let rows = board.inner.len() as i32;
+    let cols = board.inner[0].len() as i32;
+    let mut cells = mv
+        .location
+        .cells()
+        .into_iter()
+        .filter(|&(x, y)| (0..cols).contains(&x) && (0..rows).contains(&y))
+        .map(|(x, y)| ((rows - 1 - y) as usize, x as usize))
+        .collect::<Vec<_>>();
+    cells.sort_unstable();
+    cells
+}
+
+fn receive_messages(mut bot: ResMut<TbpBot>) {
+    let messages = match bot.incoming.lock() {
+        Ok(incoming) => incoming.try_iter().collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+    for message in messages {
+        match message {
+            BotMessage::Info {
+                name,
+                version,
+                author,
+                ..
+            } => {
+                info!("tbp bot {name} {version} by {author}");
+                bot.send(&FrontendMessage::Rules {});
+            }
+            BotMessage::Ready => bot.ready = true,
+            BotMessage::Error { reason } => error!("tbp bot refused to play: {reason}"),
+            BotMessage::Suggestion { moves } => {
+                bot.requested = bot.requested.saturating_sub(1);
+                // anything but the latest answer is for a piece that's gone
+                if bot.requested == 0 {
+                    bot.suggestion = Some(moves);
+                }
+            }
+        }
+    }
+}
+
+fn drive_tbp(
+    mut bot: ResMut<TbpBot>,
+    mut input: ResMut<TickInput>,
+    board: Res<Board>,
+    pieces: Query<(Entity, &Block, &BlockState)>,
+    preview: Res<CurrentBlockWithPreview<PREVIEW_COUNT>>,
+) {
+    let falling = pieces
+        .iter()
+        .find(|(_, _, state)| **state == BlockState::Falling);
+    let (Some((entity, &block, _)), true) = (falling, bot.ready) else {
+        input.advance(PlayerInput::default());
+        return;
+    };
+    if bot.piece != Some(entity) {
+        bot.piece = Some(entity);
+        bot.moves.clear();
+        bot.driver.target.clear();
+        bot.sync(&board, block, preview.preview());
+        bot.send(&FrontendMessage::Suggest);
+        bot.requested += 1;
+    }
+    if let Some(moves) = bot.suggestion.take() {
+        bot.moves = moves;
+        if let Some(mv) = bot.pick(&board, block) {
+            bot.send(&FrontendMessage::Play { mv });
+        }
+    }
+    if bot.driver.target.is_empty() {
+        // still thinking
+        input.advance(PlayerInput::default());
+        return;
+    }
+
+    let next = bot
+        .driver
+        .next_input(&board, block, &input, ACTION_INTERVAL, true)
+        .unwrap_or_else(|| {
+            // gravity got in the way, whatever happens now gets resynced
+            bot.pick(&board, block);
+            bot.expected = None;
+            bot.driver
+                .next_input(&board, block, &input, ACTION_INTERVAL, true)
+                .unwrap_or_default()
+        });
+    input.advance(next);
+}
@@ -0,0 +1,137 @@
+//! Messages of the [Tetris Bot Protocol](https://github.com/tetris-bot-protocol/tbp-spec),
+//! one JSON object per line.
+//!
+//! Only depends on serde, so it lives in the library for `src/bin/tbp_stub.rs`
+//! to use as well.
+
+use serde::{Deserialize, Serialize};
+
+/// Sent from the game to the bot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrontendMessage {
+    Rules {},
+    Start {
+        hold: Option<Piece>,
+        /// Current piece first, then the preview.
+        queue: Vec<Piece>,
+        combo: u32,
+        back_to_back: bool,
+        /// 40 rows of 10 cells, bottom row first.
+        board: Vec<Vec<Option<char>>>,
+    },
+    Stop,
+    Suggest,
+    Play {
+        #[serde(rename = "move")]
+        mv: Move,
+    },
+    NewPiece {
+        piece: Piece,
+    },
+    Quit,
+}
+
+/// Sent from the bot to the game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotMessage {
+    Info {
+        name: String,
+        version: String,
+        author: String,
+        features: Vec<String>,
+    },
+    Ready,
+    Error {
+        reason: String,
+    },
+    /// Candidate moves for the current piece, best first.
+    Suggestion {
+        moves: Vec<Move>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Piece {
+    I,
+    O,
+    T,
+    L,
+    J,
+    S,
+    Z,
+}
+
+impl Piece {
+    /// How the piece shows up in a board cell.
+    pub const fn letter(self) -> char {
+        match self {
+            Self::I => 'I',
+            Self::O => 'O',
+            Self::T => 'T',
+            Self::L => 'L',
+            Self::J => 'J',
+            Self::S => 'S',
+            Self::Z => 'Z',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    North,
+    East,
+    South,
+    West,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Spin {
+    None,
+    Mini,
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Move {
+    pub location: PieceLocation,
+    pub spin: Spin,
+}
+
+/// Where a piece locks, by the SRS center cell of the piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PieceLocation {
+    #[serde(rename = "type")]
+    pub piece: Piece,
+    pub orientation: Orientation,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl PieceLocation {
+    /// Board cells the piece covers as `(x, y)`, with y going up from the
+    /// bottom row.
+    pub fn cells(&self) -> [(i32, i32); 4] {
+        let north = match self.piece {
+            Piece::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+            Piece::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+            Piece::T => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+            Piece::L => [(-1, 0), (0, 0), (1, 0), (1, 1)],
+            Piece::J => [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+            Piece::S => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+            Piece::Z => [(-1, 1), (0, 1), (0, 0), (1, 0)],
+        };
+        north.map(|(x, y)| {
+            let (x, y) = match self.orientation {
+                Orientation::North => (x, y),
+                Orientation::East => (y, -x),
+                Orientation::South => (-x, -y),
+                Orientation::West => (-y, x),
+            };
+            (self.x + x, self.y + y)
+        })
+    }
+}
@@ -0,0 +1,258 @@
+use bevy::{input::InputSystem, prelude::*, utils::HashMap};
+
+use crate::blocks::{
+    blocks::POINT_SIZE,
+    input::{Action, PlayerInput, TouchButtons},
+};
+
+/// Touches that move less than this (in logical pixels) are taps.
+const TAP_SLOP: f32 = 10.;
+/// How far a touch has to go down to hard drop.
+const SWIPE_DISTANCE: f32 = 3. * POINT_SIZE;
+
+/// On-screen buttons and gestures for touch screens, mostly for the WASM
+/// build on phones.
+///
+/// The overlay shows up with the first touch. Touches outside of its
+/// buttons are gestures: drag sideways to move a column per
+/// [`POINT_SIZE`], tap to rotate, swipe down to hard drop.
+///
+/// Everything is read from [`Touches`], so sending synthetic
+/// [`TouchInput`] events drives it just like a real screen.
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (spawn_touch_overlay, press_touch_buttons, touch_gestures)
+                .chain()
+                .after(InputSystem),
+        );
+    }
+}
+
+#[derive(Component)]
+struct TouchOverlay;
+
+/// On-screen button triggering an action while touched.
+#[derive(Component)]
+struct TouchButton(Action);
+
+/// A touch followed as a gesture.
+#[derive(Debug, Default)]
+struct Gesture {
+    /// Columns moved so far, negative to the left.
+    columns: i32,
+}
+
+fn spawn_touch_overlay(
+    mut commands: Commands,
+    touches: Res<Touches>,
+    overlay: Query<(), With<TouchOverlay>>,
+) {
+    if !touches.any_just_pressed() || !overlay.is_empty() {
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(0.),
+                    width: Val::Percent(100.),
+                    height: Val::Px(80.),
+                    justify_content: JustifyContent::SpaceEvenly,
+                    ..default()
+                },
+                ..default()
+            },
+            TouchOverlay,
+        ))
+        .with_children(|parent| {
+            for (action, label) in [
+                (Action::MoveLeft, "<"),
+                (Action::MoveRight, ">"),
+                (Action::SoftDrop, "v"),
+                (Action::HardDrop, "Drop"),
+                (Action::RotateCcw, "CCW"),
+                (Action::RotateCw, "CW"),
+                (Action::Hold, "Hold"),
+                (Action::Pause, "||"),
+            ] {
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(11.),
+                                margin: UiRect::vertical(Val::Px(8.)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: Color::rgba(1., 1., 1., 0.15).into(),
+                            ..default()
+                        },
+                        TouchButton(action),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            label,
+                            TextStyle {
+                                font_size: 28.,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn button_at(
+    buttons: &Query<(&Node, &GlobalTransform, &TouchButton)>,
+    position: Vec2,
+) -> Option<Action> {
+    buttons
+        .iter()
+        .find(|(node, transform, _)| node.logical_rect(transform).contains(position))
+        .map(|(_, _, button)| button.0)
+}
+
+fn press_touch_buttons(
+    touches: Res<Touches>,
+    buttons: Query<(&Node, &GlobalTransform, &TouchButton)>,
+    mut touch: ResMut<TouchButtons>,
+) {
+    touch.held = touches
+        .iter()
+        .filter_map(|x| button_at(&buttons, x.position())?.button())
+        .fold(PlayerInput::default(), |acc, button| {
+            PlayerInput(acc.0 | button.0)
+        });
+    touch.pause = touches
+        .iter_just_pressed()
+        .any(|x| button_at(&buttons, x.position()) == Some(Action::Pause));
+}
+
+fn touch_gestures(
+    touches: Res<Touches>,
+    buttons: Query<(&Node, &GlobalTransform, &TouchButton)>,
+    mut gestures: Local<HashMap<u64, Gesture>>,
+    mut touch: ResMut<TouchButtons>,
+) {
+    for started in touches.iter_just_pressed() {
+        if button_at(&buttons, started.start_position()).is_none() {
+            gestures.insert(started.id(), Gesture::default());
+        }
+    }
+
+    for active in touches.iter() {
+        let Some(gesture) = gestures.get_mut(&active.id()) else {
+            continue;
+        };
+        let columns = (active.distance().x / POINT_SIZE) as i32;
+        while gesture.columns < columns {
+            gesture.columns += 1;
+            touch.tap(PlayerInput::RIGHT);
+        }
+        while gesture.columns > columns {
+            gesture.columns -= 1;
+            touch.tap(PlayerInput::LEFT);
+        }
+    }
+
+    for ended in touches.iter_just_released() {
+        let Some(gesture) = gestures.remove(&ended.id()) else {
+            continue;
+        };
+        let distance = ended.distance();
+        if gesture.columns == 0 && distance.length() < TAP_SLOP {
+            touch.tap(PlayerInput::ROTATE_CW);
+        } else if distance.y > SWIPE_DISTANCE && distance.y > distance.x.abs() {
+            // screen coordinates, y grows downwards
+            touch.tap(PlayerInput::HARD_DROP);
+        }
+    }
+    for canceled in touches.iter_just_canceled() {
+        gestures.remove(&canceled.id());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::system::RunSystemOnce,
+        input::{
+            touch::{TouchInput, TouchPhase},
+            InputPlugin,
+        },
+    };
+
+    use super::*;
+    use crate::blocks::input::step_touch;
+
+    /// Where gestures start, well away from the (unlaid) overlay buttons.
+    const START: Vec2 = Vec2::new(300., 200.);
+
+    fn touch_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin, TouchPlugin))
+            .init_resource::<TouchButtons>();
+        app
+    }
+
+    fn send(app: &mut App, phase: TouchPhase, position: Vec2) {
+        app.world.send_event(TouchInput {
+            phase,
+            position,
+            force: None,
+            id: 0,
+        });
+        app.update();
+    }
+
+    /// Touches at `START`, moves through `path` and lets go at its end.
+    fn gesture(path: &[Vec2]) -> Vec<PlayerInput> {
+        let mut app = touch_app();
+        send(&mut app, TouchPhase::Started, START);
+        for &offset in path {
+            send(&mut app, TouchPhase::Moved, START + offset);
+        }
+        let end = path.last().copied().unwrap_or_default();
+        send(&mut app, TouchPhase::Ended, START + end);
+
+        let mut taps = Vec::new();
+        for _ in 0..32 {
+            app.world.run_system_once(step_touch);
+            let current = app.world.resource::<TouchButtons>().current();
+            if current != PlayerInput::default() {
+                taps.push(current);
+            }
+        }
+        taps
+    }
+
+    #[test]
+    fn tap_rotates() {
+        assert_eq!(gesture(&[]), [PlayerInput::ROTATE_CW]);
+        assert_eq!(gesture(&[Vec2::new(2., 3.)]), [PlayerInput::ROTATE_CW]);
+    }
+
+    #[test]
+    fn swipe_down_hard_drops() {
+        let down = Vec2::new(0., SWIPE_DISTANCE + 1.);
+        assert_eq!(gesture(&[down / 2., down]), [PlayerInput::HARD_DROP]);
+        // not far enough
+        assert_eq!(gesture(&[Vec2::new(0., SWIPE_DISTANCE - 1.)]), []);
+    }
+
+    #[test]
+    fn drag_moves_a_column_per_point() {
+        let left = Vec2::new(-POINT_SIZE, 0.);
+        assert_eq!(gesture(&[left, 3. * left]), [PlayerInput::LEFT; 3]);
+        assert_eq!(
+            gesture(&[Vec2::new(2.5 * POINT_SIZE, 0.)]),
+            [PlayerInput::RIGHT; 2],
+        );
+    }
+}